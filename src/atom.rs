@@ -0,0 +1,328 @@
+use crate::error::{XcbError, XcbErrorParser};
+use bstr::{BStr, BString, ByteSlice};
+use std::collections::{HashMap, HashSet};
+use std::{ptr, slice};
+use xcb_dl::ffi::*;
+use xcb_dl::Xcb;
+
+static PREDEFINED_ATOMS: [(xcb_atom_t, &str); 68] = [
+    (XCB_ATOM_PRIMARY, "PRIMARY"),
+    (XCB_ATOM_SECONDARY, "SECONDARY"),
+    (XCB_ATOM_ARC, "ARC"),
+    (XCB_ATOM_ATOM, "ATOM"),
+    (XCB_ATOM_BITMAP, "BITMAP"),
+    (XCB_ATOM_CARDINAL, "CARDINAL"),
+    (XCB_ATOM_COLORMAP, "COLORMAP"),
+    (XCB_ATOM_CURSOR, "CURSOR"),
+    (XCB_ATOM_CUT_BUFFER0, "CUT_BUFFER0"),
+    (XCB_ATOM_CUT_BUFFER1, "CUT_BUFFER1"),
+    (XCB_ATOM_CUT_BUFFER2, "CUT_BUFFER2"),
+    (XCB_ATOM_CUT_BUFFER3, "CUT_BUFFER3"),
+    (XCB_ATOM_CUT_BUFFER4, "CUT_BUFFER4"),
+    (XCB_ATOM_CUT_BUFFER5, "CUT_BUFFER5"),
+    (XCB_ATOM_CUT_BUFFER6, "CUT_BUFFER6"),
+    (XCB_ATOM_CUT_BUFFER7, "CUT_BUFFER7"),
+    (XCB_ATOM_DRAWABLE, "DRAWABLE"),
+    (XCB_ATOM_FONT, "FONT"),
+    (XCB_ATOM_INTEGER, "INTEGER"),
+    (XCB_ATOM_PIXMAP, "PIXMAP"),
+    (XCB_ATOM_POINT, "POINT"),
+    (XCB_ATOM_RECTANGLE, "RECTANGLE"),
+    (XCB_ATOM_RESOURCE_MANAGER, "RESOURCE_MANAGER"),
+    (XCB_ATOM_RGB_COLOR_MAP, "RGB_COLOR_MAP"),
+    (XCB_ATOM_RGB_BEST_MAP, "RGB_BEST_MAP"),
+    (XCB_ATOM_RGB_BLUE_MAP, "RGB_BLUE_MAP"),
+    (XCB_ATOM_RGB_DEFAULT_MAP, "RGB_DEFAULT_MAP"),
+    (XCB_ATOM_RGB_GRAY_MAP, "RGB_GRAY_MAP"),
+    (XCB_ATOM_RGB_GREEN_MAP, "RGB_GREEN_MAP"),
+    (XCB_ATOM_RGB_RED_MAP, "RGB_RED_MAP"),
+    (XCB_ATOM_STRING, "STRING"),
+    (XCB_ATOM_VISUALID, "VISUALID"),
+    (XCB_ATOM_WINDOW, "WINDOW"),
+    (XCB_ATOM_WM_COMMAND, "WM_COMMAND"),
+    (XCB_ATOM_WM_HINTS, "WM_HINTS"),
+    (XCB_ATOM_WM_CLIENT_MACHINE, "WM_CLIENT_MACHINE"),
+    (XCB_ATOM_WM_ICON_NAME, "WM_ICON_NAME"),
+    (XCB_ATOM_WM_ICON_SIZE, "WM_ICON_SIZE"),
+    (XCB_ATOM_WM_NAME, "WM_NAME"),
+    (XCB_ATOM_WM_NORMAL_HINTS, "WM_NORMAL_HINTS"),
+    (XCB_ATOM_WM_SIZE_HINTS, "WM_SIZE_HINTS"),
+    (XCB_ATOM_WM_ZOOM_HINTS, "WM_ZOOM_HINTS"),
+    (XCB_ATOM_MIN_SPACE, "MIN_SPACE"),
+    (XCB_ATOM_NORM_SPACE, "NORM_SPACE"),
+    (XCB_ATOM_MAX_SPACE, "MAX_SPACE"),
+    (XCB_ATOM_END_SPACE, "END_SPACE"),
+    (XCB_ATOM_SUPERSCRIPT_X, "SUPERSCRIPT_X"),
+    (XCB_ATOM_SUPERSCRIPT_Y, "SUPERSCRIPT_Y"),
+    (XCB_ATOM_SUBSCRIPT_X, "SUBSCRIPT_X"),
+    (XCB_ATOM_SUBSCRIPT_Y, "SUBSCRIPT_Y"),
+    (XCB_ATOM_UNDERLINE_POSITION, "UNDERLINE_POSITION"),
+    (XCB_ATOM_UNDERLINE_THICKNESS, "UNDERLINE_THICKNESS"),
+    (XCB_ATOM_STRIKEOUT_ASCENT, "STRIKEOUT_ASCENT"),
+    (XCB_ATOM_STRIKEOUT_DESCENT, "STRIKEOUT_DESCENT"),
+    (XCB_ATOM_ITALIC_ANGLE, "ITALIC_ANGLE"),
+    (XCB_ATOM_X_HEIGHT, "X_HEIGHT"),
+    (XCB_ATOM_QUAD_WIDTH, "QUAD_WIDTH"),
+    (XCB_ATOM_WEIGHT, "WEIGHT"),
+    (XCB_ATOM_POINT_SIZE, "POINT_SIZE"),
+    (XCB_ATOM_RESOLUTION, "RESOLUTION"),
+    (XCB_ATOM_COPYRIGHT, "COPYRIGHT"),
+    (XCB_ATOM_NOTICE, "NOTICE"),
+    (XCB_ATOM_FONT_NAME, "FONT_NAME"),
+    (XCB_ATOM_FAMILY_NAME, "FAMILY_NAME"),
+    (XCB_ATOM_FULL_NAME, "FULL_NAME"),
+    (XCB_ATOM_CAP_HEIGHT, "CAP_HEIGHT"),
+    (XCB_ATOM_WM_CLASS, "WM_CLASS"),
+    (XCB_ATOM_WM_TRANSIENT_FOR, "WM_TRANSIENT_FOR"),
+];
+
+/// Returns the name of one of the atoms predefined by the core protocol.
+pub fn predefined_atom_name(atom: xcb_atom_t) -> Option<&'static str> {
+    match atom {
+        1..=68 => Some(PREDEFINED_ATOMS[atom as usize - 1].1),
+        _ => None,
+    }
+}
+
+/// Returns the atom predefined by the core protocol with the given name.
+pub fn predefined_atom(name: &[u8]) -> Option<xcb_atom_t> {
+    PREDEFINED_ATOMS
+        .iter()
+        .find(|(_, n)| n.as_bytes() == name)
+        .map(|(atom, _)| *atom)
+}
+
+/// Interns atoms with a single round trip.
+///
+/// The second element of each tuple is the `only_if_exists` flag. Atoms that do not exist
+/// are returned as `XCB_NONE`.
+pub unsafe fn intern_atoms(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    names: &[(&[u8], bool)],
+) -> Result<Vec<xcb_atom_t>, XcbError> {
+    let mut cookies = vec![];
+    for &(name, only_if_exists) in names {
+        cookies.push(xcb.xcb_intern_atom(
+            errors.c,
+            only_if_exists as u8,
+            name.len() as _,
+            name.as_ptr() as *const _,
+        ));
+    }
+    let mut atoms = Vec::with_capacity(names.len());
+    let mut cookies = cookies.into_iter();
+    while let Some(cookie) = cookies.next() {
+        let mut err = ptr::null_mut();
+        let reply = xcb.xcb_intern_atom_reply(errors.c, cookie, &mut err);
+        match errors.check(xcb, reply, err) {
+            Ok(reply) => atoms.push(reply.atom),
+            Err(e) => {
+                for cookie in cookies {
+                    xcb.xcb_discard_reply(errors.c, cookie.sequence);
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(atoms)
+}
+
+/// Defines a struct of atoms that are interned with a single round trip.
+///
+/// Fields are named after the atoms. Fields marked with `#[only_if_exists]` have type
+/// `Option<xcb_atom_t>` and are `None` if the atom has never been interned.
+///
+/// ```
+/// xcb_dl_util::atoms! {
+///     pub struct EwmhAtoms {
+///         _NET_WM_NAME,
+///         UTF8_STRING,
+///         WM_PROTOCOLS,
+///         #[only_if_exists]
+///         _KDE_NET_WM_WINDOW_TYPE_OVERRIDE,
+///     }
+/// }
+/// ```
+///
+/// This generates `unsafe fn intern(xcb: &Xcb, errors: &XcbErrorParser) -> Result<EwmhAtoms,
+/// XcbError>`.
+#[macro_export]
+macro_rules! atoms {
+    ($(#[$meta:meta])* $vis:vis struct $name:ident { $($body:tt)* }) => {
+        $crate::atoms!(@munch [$(#[$meta])* $vis struct $name] [] $($body)*);
+    };
+    (@munch $head:tt [$($acc:tt)*] #[only_if_exists] $field:ident $(, $($rest:tt)*)?) => {
+        $crate::atoms!(@munch $head [$($acc)* ($field, true)] $($($rest)*)?);
+    };
+    (@munch $head:tt [$($acc:tt)*] $field:ident $(, $($rest:tt)*)?) => {
+        $crate::atoms!(@munch $head [$($acc)* ($field, false)] $($($rest)*)?);
+    };
+    (@munch [$(#[$meta:meta])* $vis:vis struct $name:ident] [$(($field:ident, $only:tt))*]) => {
+        $(#[$meta])*
+        #[allow(non_snake_case)]
+        #[derive(Copy, Clone, Debug)]
+        $vis struct $name {
+            $(pub $field: $crate::atoms!(@ty $only),)*
+        }
+
+        impl $name {
+            #[allow(dead_code)]
+            pub unsafe fn intern(
+                xcb: &$crate::__private::xcb_dl::Xcb,
+                errors: &$crate::error::XcbErrorParser,
+            ) -> Result<Self, $crate::error::XcbError> {
+                let names: &[(&[u8], bool)] = &[
+                    $((stringify!($field).as_bytes(), $only),)*
+                ];
+                let atoms = $crate::atom::intern_atoms(xcb, errors, names)?;
+                let mut atoms = atoms.into_iter();
+                Ok(Self {
+                    $($field: $crate::atoms!(@value $only, atoms.next().unwrap()),)*
+                })
+            }
+        }
+    };
+    (@ty true) => { Option<$crate::__private::xcb_dl::ffi::xcb_atom_t> };
+    (@ty false) => { $crate::__private::xcb_dl::ffi::xcb_atom_t };
+    (@value true, $atom:expr) => {
+        match $atom {
+            $crate::__private::xcb_dl::ffi::XCB_NONE => None,
+            atom => Some(atom),
+        }
+    };
+    (@value false, $atom:expr) => { $atom };
+}
+
+/// A cache of interned atoms and atom names.
+///
+/// The predefined atoms are known without a round trip.
+#[derive(Debug)]
+pub struct XcbAtomCache {
+    atoms: HashMap<BString, xcb_atom_t>,
+    names: HashMap<xcb_atom_t, BString>,
+}
+
+impl XcbAtomCache {
+    pub fn new() -> Self {
+        let mut slf = Self {
+            atoms: HashMap::new(),
+            names: HashMap::new(),
+        };
+        for (atom, name) in &PREDEFINED_ATOMS {
+            slf.insert(*atom, name.as_bytes().as_bstr().to_owned());
+        }
+        slf
+    }
+
+    /// Returns the atom if it is cached.
+    pub fn get(&self, name: impl AsRef<[u8]>) -> Option<xcb_atom_t> {
+        self.atoms.get(name.as_ref().as_bstr()).copied()
+    }
+
+    /// Returns the name of the atom if it is cached.
+    pub fn get_name(&self, atom: xcb_atom_t) -> Option<&BStr> {
+        self.names.get(&atom).map(|n| n.as_bstr())
+    }
+
+    pub unsafe fn intern(
+        &mut self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        name: impl AsRef<[u8]>,
+    ) -> Result<xcb_atom_t, XcbError> {
+        Ok(self.intern_all(xcb, errors, &[name])?[0])
+    }
+
+    /// Interns all names that are not yet cached with a single round trip.
+    pub unsafe fn intern_all<N: AsRef<[u8]>>(
+        &mut self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        names: &[N],
+    ) -> Result<Vec<xcb_atom_t>, XcbError> {
+        let mut seen = HashSet::new();
+        let missing: Vec<_> = names
+            .iter()
+            .map(|n| n.as_ref())
+            .filter(|n| !self.atoms.contains_key(n.as_bstr()) && seen.insert(*n))
+            .map(|n| (n, false))
+            .collect();
+        let atoms = intern_atoms(xcb, errors, &missing)?;
+        for ((name, _), atom) in missing.iter().zip(atoms) {
+            self.insert(atom, name.as_bstr().to_owned());
+        }
+        Ok(names
+            .iter()
+            .map(|n| self.atoms[n.as_ref().as_bstr()])
+            .collect())
+    }
+
+    pub unsafe fn name(
+        &mut self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        atom: xcb_atom_t,
+    ) -> Result<&BStr, XcbError> {
+        self.fetch_names(xcb, errors, &[atom])?;
+        Ok(self.names[&atom].as_bstr())
+    }
+
+    /// Retrieves the names of all atoms that are not yet cached with a single round trip.
+    pub unsafe fn names(
+        &mut self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        atoms: &[xcb_atom_t],
+    ) -> Result<Vec<&BStr>, XcbError> {
+        self.fetch_names(xcb, errors, atoms)?;
+        Ok(atoms.iter().map(|a| self.names[a].as_bstr()).collect())
+    }
+
+    unsafe fn fetch_names(
+        &mut self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        atoms: &[xcb_atom_t],
+    ) -> Result<(), XcbError> {
+        let mut seen = HashSet::new();
+        let mut cookies = vec![];
+        for &atom in atoms {
+            if !self.names.contains_key(&atom) && seen.insert(atom) {
+                cookies.push((atom, xcb.xcb_get_atom_name(errors.c, atom)));
+            }
+        }
+        let mut cookies = cookies.into_iter();
+        while let Some((atom, cookie)) = cookies.next() {
+            let mut err = ptr::null_mut();
+            let reply = xcb.xcb_get_atom_name_reply(errors.c, cookie, &mut err);
+            match errors.check(xcb, reply, err) {
+                Ok(reply) => {
+                    let name = slice::from_raw_parts(
+                        xcb.xcb_get_atom_name_name(&*reply) as *const u8,
+                        reply.name_len as _,
+                    );
+                    self.insert(atom, name.as_bstr().to_owned());
+                }
+                Err(e) => {
+                    for (_, cookie) in cookies {
+                        xcb.xcb_discard_reply(errors.c, cookie.sequence);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, atom: xcb_atom_t, name: BString) {
+        self.atoms.insert(name.clone(), atom);
+        self.names.insert(atom, name);
+    }
+}
+
+impl Default for XcbAtomCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
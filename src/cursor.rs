@@ -1,4 +1,7 @@
 use crate::error::{XcbError, XcbErrorParser};
+use crate::property::{
+    get_property_limited, XcbGetPropertyError, XcbPropertyLimit, XcbPropertyLimitPolicy,
+};
 use crate::render::{find_standard_format, XcbPictFormat};
 use bstr::{BStr, BString, ByteSlice, ByteVec};
 use byteorder::{LittleEndian, ReadBytesExt};
@@ -8,7 +11,7 @@ use std::convert::TryInto;
 use std::fmt::{Debug, Formatter};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::{env, io, ptr, slice, str};
+use std::{env, io, ptr, str};
 use thiserror::Error;
 use xcb_dl::ffi::*;
 use xcb_dl::Xcb;
@@ -25,6 +28,9 @@ const DEPTH: u8 = 32;
 
 const HEADER_SIZE: u32 = 16;
 
+const RESOURCE_MANAGER_STEP: u32 = 64 * 1024;
+const RESOURCE_MANAGER_LIMIT: u32 = 16 * 1024 * 1024;
+
 #[derive(Debug)]
 pub struct XcbCursorContext {
     c: *mut xcb_connection_t,
@@ -440,41 +446,32 @@ unsafe fn resource_values(
     res.2 = screen.root;
     res.3 = screen.root_visual;
 
-    let cookie = xcb.xcb_get_property(
-        c,
-        0,
+    let limit = XcbPropertyLimit {
+        max_bytes: RESOURCE_MANAGER_LIMIT,
+        policy: XcbPropertyLimitPolicy::Error,
+    };
+    let value = match get_property_limited::<u8>(
+        xcb,
+        errors,
         screen.root,
         XCB_ATOM_RESOURCE_MANAGER,
         XCB_ATOM_STRING,
-        0,
-        1024 * 1024 * 256,
-    );
-    let mut err = ptr::null_mut();
-    let reply = xcb.xcb_get_property_reply(c, cookie, &mut err);
-    let reply = match errors.check(xcb, reply, err) {
-        Ok(r) => r,
+        false,
+        RESOURCE_MANAGER_STEP,
+        limit,
+    ) {
+        Ok(v) => v.value,
+        Err(XcbGetPropertyError::Unset) => return res,
         Err(e) => {
             log::warn!("Could not read the resource manager property: {}", e);
             return res;
         }
     };
-    if reply.type_ == 0 {
-        // Property not set.
-        return res;
-    }
-    if reply.type_ != XCB_ATOM_STRING || reply.format != 8 {
-        log::warn!("Resource manager property has an invalid format");
-        return res;
-    }
-    let value = slice::from_raw_parts(
-        xcb.xcb_get_property_value(&*reply) as *mut u8,
-        reply.value_len as _,
-    );
 
     let mut xcursor_size = None;
     let mut xft_dpi = None;
     // https://github.com/intellij-rust/intellij-rust/issues/8021
-    for line in <[u8]>::split(value, |b| *b == b'\n') {
+    for line in <[u8]>::split(&value, |b| *b == b'\n') {
         let (name, value) = match line.iter().position(|b| *b == b':') {
             Some(v) => line.split_at(v),
             _ => continue,
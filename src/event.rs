@@ -0,0 +1,68 @@
+use crate::void::XcbPendingCommand;
+use std::{mem, ptr};
+use xcb_dl::ffi::*;
+use xcb_dl::Xcb;
+
+/// Sends an event to a window.
+///
+/// Events shorter than 32 bytes are padded with zeros.
+pub unsafe fn send_event<T: Copy>(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    propagate: bool,
+    destination: xcb_window_t,
+    event_mask: u32,
+    event: &T,
+) -> XcbPendingCommand {
+    assert!(mem::size_of::<T>() <= 32);
+    let mut buf = [0; 32];
+    ptr::copy_nonoverlapping(
+        event as *const T as *const u8,
+        buf.as_mut_ptr() as *mut u8,
+        mem::size_of::<T>(),
+    );
+    xcb.xcb_send_event_checked(c, propagate as u8, destination, event_mask, &buf)
+        .into()
+}
+
+/// Sends a ClientMessage event with 32-bit data.
+pub unsafe fn send_client_message(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    destination: xcb_window_t,
+    event_mask: u32,
+    window: xcb_window_t,
+    type_: xcb_atom_t,
+    data: [u32; 5],
+) -> XcbPendingCommand {
+    let event = xcb_client_message_event_t {
+        response_type: XCB_CLIENT_MESSAGE,
+        format: 32,
+        window,
+        type_,
+        data: xcb_client_message_data_t { data32: data },
+        ..Default::default()
+    };
+    send_event(xcb, c, false, destination, event_mask, &event)
+}
+
+/// Sends a ClientMessage event to the root window, as required for messages addressed to
+/// the window manager.
+pub unsafe fn send_root_message(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    root: xcb_window_t,
+    window: xcb_window_t,
+    type_: xcb_atom_t,
+    data: [u32; 5],
+) -> XcbPendingCommand {
+    send_client_message(
+        xcb,
+        c,
+        root,
+        XCB_EVENT_MASK_SUBSTRUCTURE_NOTIFY | XCB_EVENT_MASK_SUBSTRUCTURE_REDIRECT,
+        window,
+        type_,
+        data,
+    )
+}
@@ -0,0 +1,2136 @@
+//! Helpers for the Extended Window Manager Hints.
+
+use crate::error::{XcbError, XcbErrorParser};
+use crate::event::send_root_message;
+use crate::format::{XcbPropertyStruct, XcbPropertyStructError};
+use crate::hint::{XcbFrameExtents, XcbGeometry};
+use crate::property::{
+    get_property_limited, get_u32_property, get_u32_value, request_property, set_property,
+    truncate_after, XcbGetPropertyError, XcbPendingProperty, XcbPropertyLimit,
+    XcbPropertyLimitPolicy,
+};
+use crate::text::{join_strings, split_strings, XcbTextEncoding};
+use crate::void::{XcbPendingCommand, XcbPendingCommands};
+use crate::xcb_box::XcbBox;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use xcb_dl::ffi::*;
+use xcb_dl::Xcb;
+
+/// The value of _NET_WM_DESKTOP for windows that are shown on all desktops.
+pub const ALL_DESKTOPS: u32 = 0xffff_ffff;
+
+/// The number of atoms of _NET_WM_STATE that are read.
+const STATE_LEN: usize = 64;
+/// The number of window types that are read. Further types are ignored.
+const WINDOW_TYPE_LEN: usize = 16;
+
+/// The number of elements of list properties of the root window that are read.
+const ROOT_LIST_LEN: usize = 64 * 1024;
+/// The number of bytes of the _NET_WM_NAME of the check window that are read.
+const WM_NAME_LIMIT: u32 = 4096;
+/// The number of bytes of _NET_DESKTOP_NAMES that are read.
+const DESKTOP_NAMES_LIMIT: u32 = 64 * 1024;
+
+const ICON_STEP: u32 = 64 * 1024;
+const ICON_LIMIT: u32 = 16 * 1024 * 1024;
+
+crate::atoms! {
+    /// The atoms used by the EWMH helpers.
+    pub struct XcbEwmhAtoms {
+        _NET_WM_STATE,
+        _NET_WM_STATE_MODAL,
+        _NET_WM_STATE_STICKY,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_SHADED,
+        _NET_WM_STATE_SKIP_TASKBAR,
+        _NET_WM_STATE_SKIP_PAGER,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_BELOW,
+        _NET_WM_STATE_DEMANDS_ATTENTION,
+        _NET_WM_STATE_FOCUSED,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DESKTOP,
+        _NET_WM_WINDOW_TYPE_DOCK,
+        _NET_WM_WINDOW_TYPE_TOOLBAR,
+        _NET_WM_WINDOW_TYPE_MENU,
+        _NET_WM_WINDOW_TYPE_UTILITY,
+        _NET_WM_WINDOW_TYPE_SPLASH,
+        _NET_WM_WINDOW_TYPE_DIALOG,
+        _NET_WM_WINDOW_TYPE_DROPDOWN_MENU,
+        _NET_WM_WINDOW_TYPE_POPUP_MENU,
+        _NET_WM_WINDOW_TYPE_TOOLTIP,
+        _NET_WM_WINDOW_TYPE_NOTIFICATION,
+        _NET_WM_WINDOW_TYPE_COMBO,
+        _NET_WM_WINDOW_TYPE_DND,
+        _NET_WM_WINDOW_TYPE_NORMAL,
+        _NET_WM_ICON,
+        _NET_WM_STRUT,
+        _NET_WM_STRUT_PARTIAL,
+        _NET_WORKAREA,
+        _NET_SUPPORTED,
+        _NET_SUPPORTING_WM_CHECK,
+        _NET_WM_NAME,
+        UTF8_STRING,
+        _NET_CLIENT_LIST,
+        _NET_CLIENT_LIST_STACKING,
+        _NET_NUMBER_OF_DESKTOPS,
+        _NET_DESKTOP_NAMES,
+        _NET_CURRENT_DESKTOP,
+        _NET_DESKTOP_VIEWPORT,
+        _NET_DESKTOP_GEOMETRY,
+        _NET_ACTIVE_WINDOW,
+        _NET_SHOWING_DESKTOP,
+        _NET_CLOSE_WINDOW,
+        _NET_WM_DESKTOP,
+        _NET_WM_MOVERESIZE,
+        _NET_FRAME_EXTENTS,
+        _GTK_FRAME_EXTENTS,
+        _NET_REQUEST_FRAME_EXTENTS,
+    }
+}
+
+/// The source of a request sent to the window manager.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbSourceIndication {
+    /// The client supports an older version of the spec.
+    Legacy,
+    Application,
+    Pager,
+}
+
+impl From<u32> for XcbSourceIndication {
+    /// Unknown values are treated like `Legacy`.
+    fn from(v: u32) -> Self {
+        match v {
+            1 => XcbSourceIndication::Application,
+            2 => XcbSourceIndication::Pager,
+            _ => XcbSourceIndication::Legacy,
+        }
+    }
+}
+
+impl From<XcbSourceIndication> for u32 {
+    fn from(s: XcbSourceIndication) -> Self {
+        match s {
+            XcbSourceIndication::Legacy => 0,
+            XcbSourceIndication::Application => 1,
+            XcbSourceIndication::Pager => 2,
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// The states in the _NET_WM_STATE property.
+    #[derive(Default)]
+    pub struct XcbNetWmState: u32 {
+        const MODAL = 1 << 0;
+        const STICKY = 1 << 1;
+        const MAXIMIZED_VERT = 1 << 2;
+        const MAXIMIZED_HORZ = 1 << 3;
+        const SHADED = 1 << 4;
+        const SKIP_TASKBAR = 1 << 5;
+        const SKIP_PAGER = 1 << 6;
+        const HIDDEN = 1 << 7;
+        const FULLSCREEN = 1 << 8;
+        const ABOVE = 1 << 9;
+        const BELOW = 1 << 10;
+        const DEMANDS_ATTENTION = 1 << 11;
+        const FOCUSED = 1 << 12;
+    }
+}
+
+impl XcbNetWmState {
+    fn atoms(atoms: &XcbEwmhAtoms) -> [(Self, xcb_atom_t); 13] {
+        [
+            (Self::MODAL, atoms._NET_WM_STATE_MODAL),
+            (Self::STICKY, atoms._NET_WM_STATE_STICKY),
+            (Self::MAXIMIZED_VERT, atoms._NET_WM_STATE_MAXIMIZED_VERT),
+            (Self::MAXIMIZED_HORZ, atoms._NET_WM_STATE_MAXIMIZED_HORZ),
+            (Self::SHADED, atoms._NET_WM_STATE_SHADED),
+            (Self::SKIP_TASKBAR, atoms._NET_WM_STATE_SKIP_TASKBAR),
+            (Self::SKIP_PAGER, atoms._NET_WM_STATE_SKIP_PAGER),
+            (Self::HIDDEN, atoms._NET_WM_STATE_HIDDEN),
+            (Self::FULLSCREEN, atoms._NET_WM_STATE_FULLSCREEN),
+            (Self::ABOVE, atoms._NET_WM_STATE_ABOVE),
+            (Self::BELOW, atoms._NET_WM_STATE_BELOW),
+            (
+                Self::DEMANDS_ATTENTION,
+                atoms._NET_WM_STATE_DEMANDS_ATTENTION,
+            ),
+            (Self::FOCUSED, atoms._NET_WM_STATE_FOCUSED),
+        ]
+    }
+
+    /// Returns the state corresponding to an atom or the empty set if the atom is unknown.
+    pub fn from_atom(atoms: &XcbEwmhAtoms, atom: xcb_atom_t) -> Self {
+        Self::from_atoms(atoms, &[atom])
+    }
+
+    /// Converts a list of atoms. Unknown atoms are ignored.
+    pub fn from_atoms(atoms: &XcbEwmhAtoms, list: &[xcb_atom_t]) -> Self {
+        let mut res = Self::empty();
+        for (state, atom) in Self::atoms(atoms) {
+            if atom != XCB_NONE && list.contains(&atom) {
+                res |= state;
+            }
+        }
+        res
+    }
+
+    pub fn to_atoms(self, atoms: &XcbEwmhAtoms) -> Vec<xcb_atom_t> {
+        Self::atoms(atoms)
+            .iter()
+            .filter(|(state, _)| self.contains(*state))
+            .map(|(_, atom)| *atom)
+            .collect()
+    }
+}
+
+/// Reads the _NET_WM_STATE property. Unknown states are ignored.
+///
+/// Only the first 64 atoms are read.
+pub unsafe fn get_net_wm_state(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> Result<XcbNetWmState, XcbGetPropertyError> {
+    let list = get_u32_property(
+        xcb,
+        errors,
+        window,
+        atoms._NET_WM_STATE,
+        XCB_ATOM_ATOM,
+        STATE_LEN,
+    )?;
+    Ok(XcbNetWmState::from_atoms(atoms, &list))
+}
+
+/// Sets the _NET_WM_STATE property. This should only be done by the window manager or
+/// by clients before mapping their window.
+pub unsafe fn set_net_wm_state(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+    state: XcbNetWmState,
+) -> XcbPendingCommands {
+    let list = state.to_atoms(atoms);
+    set_property(xcb, c, window, atoms._NET_WM_STATE, XCB_ATOM_ATOM, &list)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbNetWmStateAction {
+    Remove,
+    Add,
+    Toggle,
+}
+
+impl TryFrom<u32> for XcbNetWmStateAction {
+    type Error = u32;
+
+    fn try_from(v: u32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(XcbNetWmStateAction::Remove),
+            1 => Ok(XcbNetWmStateAction::Add),
+            2 => Ok(XcbNetWmStateAction::Toggle),
+            _ => Err(v),
+        }
+    }
+}
+
+impl From<XcbNetWmStateAction> for u32 {
+    fn from(a: XcbNetWmStateAction) -> Self {
+        match a {
+            XcbNetWmStateAction::Remove => 0,
+            XcbNetWmStateAction::Add => 1,
+            XcbNetWmStateAction::Toggle => 2,
+        }
+    }
+}
+
+/// A _NET_WM_STATE request.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct XcbNetWmStateRequest {
+    pub window: xcb_window_t,
+    pub action: XcbNetWmStateAction,
+    /// The states to change. A decoded message contains the known states among its two
+    /// properties.
+    pub states: XcbNetWmState,
+    pub source: XcbSourceIndication,
+}
+
+impl XcbNetWmStateRequest {
+    /// Decodes a client message. Returns `None` if it is not a valid _NET_WM_STATE message.
+    pub fn decode(atoms: &XcbEwmhAtoms, event: &xcb_client_message_event_t) -> Option<Self> {
+        if event.type_ != atoms._NET_WM_STATE || event.format != 32 {
+            return None;
+        }
+        let data = unsafe { event.data.data32 };
+        Some(Self {
+            window: event.window,
+            action: XcbNetWmStateAction::try_from(data[0]).ok()?,
+            states: XcbNetWmState::from_atoms(atoms, &data[1..3]),
+            source: data[3].into(),
+        })
+    }
+
+    /// Applies the request to the current state of the window.
+    ///
+    /// When toggling, each state is toggled independently.
+    pub fn apply(&self, state: XcbNetWmState) -> XcbNetWmState {
+        match self.action {
+            XcbNetWmStateAction::Remove => state - self.states,
+            XcbNetWmStateAction::Add => state | self.states,
+            XcbNetWmStateAction::Toggle => state ^ self.states,
+        }
+    }
+}
+
+/// Asks the window manager to change the state of a mapped window.
+///
+/// Each message carries at most two states. If the request contains more than two states,
+/// multiple messages are sent.
+pub unsafe fn request_net_wm_state(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    request: &XcbNetWmStateRequest,
+) -> XcbPendingCommands {
+    let mut commands = XcbPendingCommands::new();
+    for pair in request.states.to_atoms(atoms).chunks(2) {
+        let data = [
+            request.action.into(),
+            pair[0],
+            pair.get(1).copied().unwrap_or(XCB_NONE),
+            request.source.into(),
+            0,
+        ];
+        commands.push(send_root_message(
+            xcb,
+            c,
+            root,
+            request.window,
+            atoms._NET_WM_STATE,
+            data,
+        ));
+    }
+    commands
+}
+
+/// A window type from the _NET_WM_WINDOW_TYPE property.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbNetWmWindowType {
+    Desktop,
+    Dock,
+    Toolbar,
+    Menu,
+    Utility,
+    Splash,
+    Dialog,
+    DropdownMenu,
+    PopupMenu,
+    Tooltip,
+    Notification,
+    Combo,
+    Dnd,
+    Normal,
+    /// A non-standard type such as _KDE_NET_WM_WINDOW_TYPE_OVERRIDE.
+    Other(xcb_atom_t),
+}
+
+impl XcbNetWmWindowType {
+    fn atoms(atoms: &XcbEwmhAtoms) -> [(Self, xcb_atom_t); 14] {
+        [
+            (Self::Desktop, atoms._NET_WM_WINDOW_TYPE_DESKTOP),
+            (Self::Dock, atoms._NET_WM_WINDOW_TYPE_DOCK),
+            (Self::Toolbar, atoms._NET_WM_WINDOW_TYPE_TOOLBAR),
+            (Self::Menu, atoms._NET_WM_WINDOW_TYPE_MENU),
+            (Self::Utility, atoms._NET_WM_WINDOW_TYPE_UTILITY),
+            (Self::Splash, atoms._NET_WM_WINDOW_TYPE_SPLASH),
+            (Self::Dialog, atoms._NET_WM_WINDOW_TYPE_DIALOG),
+            (Self::DropdownMenu, atoms._NET_WM_WINDOW_TYPE_DROPDOWN_MENU),
+            (Self::PopupMenu, atoms._NET_WM_WINDOW_TYPE_POPUP_MENU),
+            (Self::Tooltip, atoms._NET_WM_WINDOW_TYPE_TOOLTIP),
+            (Self::Notification, atoms._NET_WM_WINDOW_TYPE_NOTIFICATION),
+            (Self::Combo, atoms._NET_WM_WINDOW_TYPE_COMBO),
+            (Self::Dnd, atoms._NET_WM_WINDOW_TYPE_DND),
+            (Self::Normal, atoms._NET_WM_WINDOW_TYPE_NORMAL),
+        ]
+    }
+
+    pub fn from_atom(atoms: &XcbEwmhAtoms, atom: xcb_atom_t) -> Self {
+        Self::atoms(atoms)
+            .iter()
+            .find(|(_, a)| *a == atom)
+            .map(|(ty, _)| *ty)
+            .unwrap_or(XcbNetWmWindowType::Other(atom))
+    }
+
+    pub fn atom(self, atoms: &XcbEwmhAtoms) -> xcb_atom_t {
+        match self {
+            XcbNetWmWindowType::Other(atom) => atom,
+            _ => Self::atoms(atoms)
+                .iter()
+                .find(|(ty, _)| *ty == self)
+                .map(|(_, atom)| *atom)
+                .unwrap(),
+        }
+    }
+}
+
+/// Reads the _NET_WM_WINDOW_TYPE property in order of preference.
+pub unsafe fn get_net_wm_window_types(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> Result<Vec<XcbNetWmWindowType>, XcbGetPropertyError> {
+    let list = request_net_wm_window_type(xcb, errors.c, atoms, window).reply_limited(
+        xcb,
+        errors,
+        truncate_after(WINDOW_TYPE_LEN),
+    )?;
+    Ok(list
+        .value
+        .into_iter()
+        .map(|atom| XcbNetWmWindowType::from_atom(atoms, atom))
+        .collect())
+}
+
+/// Returns the first standard type in the _NET_WM_WINDOW_TYPE property.
+///
+/// If the property is unset or contains no standard type, returns `Dialog` if
+/// WM_TRANSIENT_FOR is set and `Normal` otherwise.
+pub unsafe fn get_net_wm_window_type(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> Result<XcbNetWmWindowType, XcbGetPropertyError> {
+    let types = request_net_wm_window_type(xcb, errors.c, atoms, window);
+    let transient_for = request_property::<u32>(
+        xcb,
+        errors.c,
+        window,
+        XCB_ATOM_WM_TRANSIENT_FOR,
+        XCB_ATOM_WINDOW,
+        false,
+        1,
+    );
+    let types = types.reply_limited(xcb, errors, truncate_after(WINDOW_TYPE_LEN));
+    let transient_for = transient_for.reply_limited(xcb, errors, truncate_after(1));
+    let types = match types {
+        Ok(t) => t.value,
+        Err(XcbGetPropertyError::Xcb(e)) => return Err(e.into()),
+        Err(_) => vec![],
+    };
+    let transient = match transient_for {
+        Ok(t) => !t.value.is_empty(),
+        Err(XcbGetPropertyError::Xcb(e)) => return Err(e.into()),
+        Err(_) => false,
+    };
+    let ty = types
+        .into_iter()
+        .map(|atom| XcbNetWmWindowType::from_atom(atoms, atom))
+        .find(|ty| !matches!(ty, XcbNetWmWindowType::Other(_)));
+    Ok(match ty {
+        Some(ty) => ty,
+        None if transient => XcbNetWmWindowType::Dialog,
+        None => XcbNetWmWindowType::Normal,
+    })
+}
+
+pub unsafe fn set_net_wm_window_types(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+    types: &[XcbNetWmWindowType],
+) -> XcbPendingCommands {
+    let list: Vec<_> = types.iter().map(|ty| ty.atom(atoms)).collect();
+    set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_WM_WINDOW_TYPE,
+        XCB_ATOM_ATOM,
+        &list,
+    )
+}
+
+unsafe fn request_net_wm_window_type(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> XcbPendingProperty<u32> {
+    request_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_WM_WINDOW_TYPE,
+        XCB_ATOM_ATOM,
+        false,
+        WINDOW_TYPE_LEN as u32,
+    )
+}
+
+/// An image from the _NET_WM_ICON property.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XcbNetWmIcon {
+    pub width: u32,
+    pub height: u32,
+    /// Non-premultiplied ARGB pixels in row-major order.
+    pub pixels: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbNetWmIconError {
+    #[error("The icon at index {index} has the invalid size {width}x{height}")]
+    InvalidSize {
+        index: usize,
+        width: u32,
+        height: u32,
+    },
+    #[error("The icon at index {index} has {len} pixels but should have {width}x{height}")]
+    WrongPixelCount {
+        index: usize,
+        width: u32,
+        height: u32,
+        len: usize,
+    },
+}
+
+impl XcbNetWmIcon {
+    /// Creates an icon from non-premultiplied RGBA bytes.
+    ///
+    /// Returns `None` if the size is empty or if `rgba` does not contain exactly
+    /// `width * height` pixels.
+    pub fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> Option<Self> {
+        if width == 0 || height == 0 || rgba.len() as u64 != width as u64 * height as u64 * 4 {
+            return None;
+        }
+        let pixels = rgba
+            .chunks_exact(4)
+            .map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]]))
+            .collect();
+        Some(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Returns the pixels as non-premultiplied RGBA bytes.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.pixels.len() * 4);
+        for p in &self.pixels {
+            let [a, r, g, b] = p.to_be_bytes();
+            rgba.extend_from_slice(&[r, g, b, a]);
+        }
+        rgba
+    }
+
+    /// Decodes the contents of the _NET_WM_ICON property.
+    ///
+    /// Entries with an empty size are skipped. An incomplete trailing entry, e.g. of a
+    /// truncated property, is dropped.
+    pub fn decode(mut data: &[u32]) -> Vec<Self> {
+        let mut icons = vec![];
+        while let [width, height, rest @ ..] = data {
+            let len = *width as u64 * *height as u64;
+            if len > rest.len() as u64 {
+                break;
+            }
+            let (pixels, rest) = rest.split_at(len as usize);
+            if len > 0 {
+                icons.push(Self {
+                    width: *width,
+                    height: *height,
+                    pixels: pixels.to_vec(),
+                });
+            }
+            data = rest;
+        }
+        icons
+    }
+
+    fn validate(&self, index: usize) -> Result<(), XcbNetWmIconError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(XcbNetWmIconError::InvalidSize {
+                index,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        if self.pixels.len() as u64 != self.width as u64 * self.height as u64 {
+            return Err(XcbNetWmIconError::WrongPixelCount {
+                index,
+                width: self.width,
+                height: self.height,
+                len: self.pixels.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Encodes icons as the contents of the _NET_WM_ICON property.
+    pub fn encode(icons: &[Self]) -> Result<Vec<u32>, XcbNetWmIconError> {
+        let mut data = vec![];
+        for (index, icon) in icons.iter().enumerate() {
+            icon.validate(index)?;
+            data.push(icon.width);
+            data.push(icon.height);
+            data.extend_from_slice(&icon.pixels);
+        }
+        Ok(data)
+    }
+
+    /// Returns the icon whose size is closest to `size`.
+    ///
+    /// The size of an icon is the larger of its width and height. If multiple icons are
+    /// equally close, the first one is returned.
+    pub fn select(icons: &[Self], size: u32) -> Option<&Self> {
+        let mut best = None;
+        let mut best_fit = i64::MAX;
+        for icon in icons {
+            let fit = (icon.width.max(icon.height) as i64 - size as i64).abs();
+            if fit < best_fit {
+                best_fit = fit;
+                best = Some(icon);
+            }
+        }
+        best
+    }
+
+    /// Like `select` but scales the icon so that its size is exactly `size`. The aspect
+    /// ratio is preserved.
+    ///
+    /// Returns `None` if `size` is 0 or if the selected icon is invalid.
+    pub fn select_scaled(icons: &[Self], size: u32) -> Option<Self> {
+        let icon = Self::select(icons, size)?;
+        let max = icon.width.max(icon.height) as u64;
+        if max == 0 {
+            return None;
+        }
+        let scale = |n: u32| ((n as u64 * size as u64 + max / 2) / max).max(1) as u32;
+        icon.scale(scale(icon.width), scale(icon.height))
+    }
+
+    /// Scales the icon with a box filter.
+    ///
+    /// Returns `None` if either size is empty or if the icon does not contain exactly
+    /// `width * height` pixels.
+    pub fn scale(&self, width: u32, height: u32) -> Option<Self> {
+        if width == 0 || height == 0 || self.validate(0).is_err() {
+            return None;
+        }
+        if width == self.width && height == self.height {
+            return Some(self.clone());
+        }
+        let range = |i: u32, dst: u32, src: u32| {
+            let start = (i as u64 * src as u64 / dst as u64) as usize;
+            let end = ((i as u64 + 1) * src as u64 / dst as u64) as usize;
+            start..end.max(start + 1)
+        };
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            let rows = range(y, height, self.height);
+            for x in 0..width {
+                let cols = range(x, width, self.width);
+                // Average in premultiplied space so that transparent pixels do not bleed.
+                let mut sum = [0u64; 4];
+                let mut n = 0;
+                for row in rows.clone() {
+                    let row = &self.pixels[row * self.width as usize..];
+                    for p in &row[cols.clone()] {
+                        let [a, r, g, b] = p.to_be_bytes();
+                        let a = a as u64;
+                        sum[0] += a;
+                        sum[1] += r as u64 * a;
+                        sum[2] += g as u64 * a;
+                        sum[3] += b as u64 * a;
+                        n += 1;
+                    }
+                }
+                let a = sum[0];
+                let c = |v: u64| v.checked_div(a).unwrap_or(0) as u8;
+                let pixel = [(a / n) as u8, c(sum[1]), c(sum[2]), c(sum[3])];
+                pixels.push(u32::from_be_bytes(pixel));
+            }
+        }
+        Some(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/// Reads the _NET_WM_ICON property.
+///
+/// At most 16 MiB are read. Icons that do not fit completely are dropped.
+pub unsafe fn get_net_wm_icon(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> Result<Vec<XcbNetWmIcon>, XcbGetPropertyError> {
+    let limit = XcbPropertyLimit {
+        max_bytes: ICON_LIMIT,
+        policy: XcbPropertyLimitPolicy::Truncate,
+    };
+    let data = get_property_limited::<u32>(
+        xcb,
+        errors,
+        window,
+        atoms._NET_WM_ICON,
+        XCB_ATOM_CARDINAL,
+        false,
+        ICON_STEP,
+        limit,
+    )?;
+    Ok(XcbNetWmIcon::decode(&data.value))
+}
+
+pub unsafe fn set_net_wm_icon(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+    icons: &[XcbNetWmIcon],
+) -> Result<XcbPendingCommands, XcbNetWmIconError> {
+    let data = XcbNetWmIcon::encode(icons)?;
+    Ok(set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_WM_ICON,
+        XCB_ATOM_CARDINAL,
+        &data,
+    ))
+}
+
+crate::xcb_property_struct! {
+    /// The contents of the _NET_WM_STRUT property.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+    pub struct XcbStrut: [u32] {
+        pub left: u32,
+        pub right: u32,
+        pub top: u32,
+        pub bottom: u32,
+    }
+}
+
+crate::xcb_property_struct! {
+    /// The contents of the _NET_WM_STRUT_PARTIAL property.
+    ///
+    /// The widths are relative to the edges of the screen. The start and end values are
+    /// inclusive.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+    pub struct XcbStrutPartial: [u32] {
+        pub left: u32,
+        pub right: u32,
+        pub top: u32,
+        pub bottom: u32,
+        pub left_start_y: u32,
+        pub left_end_y: u32,
+        pub right_start_y: u32,
+        pub right_end_y: u32,
+        pub top_start_x: u32,
+        pub top_end_x: u32,
+        pub bottom_start_x: u32,
+        pub bottom_end_x: u32,
+    }
+}
+
+impl From<XcbStrut> for XcbStrutPartial {
+    /// The struts cover the whole edges of the screen.
+    fn from(s: XcbStrut) -> Self {
+        Self {
+            left: s.left,
+            right: s.right,
+            top: s.top,
+            bottom: s.bottom,
+            left_end_y: u32::MAX,
+            right_end_y: u32::MAX,
+            top_end_x: u32::MAX,
+            bottom_end_x: u32::MAX,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbGetStrutError {
+    #[error("Could not read the property: {0}")]
+    Property(#[from] XcbGetPropertyError),
+    #[error("The property is invalid: {0}")]
+    Strut(#[from] XcbPropertyStructError),
+}
+
+/// Reads _NET_WM_STRUT_PARTIAL or, if it is not set, _NET_WM_STRUT.
+pub unsafe fn get_net_wm_strut(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> Result<XcbStrutPartial, XcbGetStrutError> {
+    let request = |property, len: usize| {
+        request_property::<u32>(
+            xcb,
+            errors.c,
+            window,
+            property,
+            XCB_ATOM_CARDINAL,
+            false,
+            len as u32,
+        )
+    };
+    let partial = request(atoms._NET_WM_STRUT_PARTIAL, XcbStrutPartial::LEN);
+    let strut = request(atoms._NET_WM_STRUT, XcbStrut::LEN);
+    let partial = partial.reply_limited(xcb, errors, truncate_after(XcbStrutPartial::LEN));
+    let strut = strut.reply_limited(xcb, errors, truncate_after(XcbStrut::LEN));
+    match partial {
+        Ok(p) => return Ok(XcbStrutPartial::from_elements(&p.value)?),
+        Err(XcbGetPropertyError::Unset) => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(XcbStrut::from_elements(&strut?.value)?.into())
+}
+
+pub unsafe fn set_net_wm_strut_partial(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+    strut: &XcbStrutPartial,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_WM_STRUT_PARTIAL,
+        XCB_ATOM_CARDINAL,
+        strut.as_elements(),
+    )
+}
+
+pub unsafe fn set_net_wm_strut(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+    strut: &XcbStrut,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_WM_STRUT,
+        XCB_ATOM_CARDINAL,
+        strut.as_elements(),
+    )
+}
+
+/// Computes the area of each monitor that is not covered by struts.
+///
+/// `screen` is the size of the root window. Since struts are relative to the edges of the
+/// screen, a strut is only applied to a monitor if its inner edge lies within the monitor.
+/// This allows panels at the inner edges of monitors in multi-monitor setups.
+///
+/// A strut whose inner edge lies beyond a monitor is ignored for that monitor even though
+/// it covers the monitor completely. Such a strut cannot be distinguished from one that
+/// belongs to a panel at the inner edge of a neighboring monitor. For example, a left strut
+/// of 1950 pixels on two 1920 pixel wide monitors reserves 30 pixels of the right monitor
+/// and none of the left one.
+pub fn compute_workareas(
+    screen: XcbGeometry,
+    monitors: &[XcbGeometry],
+    struts: &[XcbStrutPartial],
+) -> Vec<XcbGeometry> {
+    let overlaps = |start: u32, end: u32, pos: i32, len: u32| {
+        (start as i64) < pos as i64 + len as i64 && end as i64 >= pos as i64
+    };
+    let contains =
+        |edge: i64, pos: i32, len: u32| edge >= pos as i64 && edge < pos as i64 + len as i64;
+    let screen_right = screen.x as i64 + screen.width as i64;
+    let screen_bottom = screen.y as i64 + screen.height as i64;
+    monitors
+        .iter()
+        .map(|m| {
+            let mut x1 = m.x as i64;
+            let mut y1 = m.y as i64;
+            let mut x2 = x1 + m.width as i64;
+            let mut y2 = y1 + m.height as i64;
+            for s in struts {
+                let left = screen.x as i64 + s.left as i64;
+                if s.left > 0
+                    && contains(left, m.x, m.width)
+                    && overlaps(s.left_start_y, s.left_end_y, m.y, m.height)
+                {
+                    x1 = x1.max(left);
+                }
+                let right = screen_right - s.right as i64;
+                if s.right > 0
+                    && contains(right, m.x + 1, m.width)
+                    && overlaps(s.right_start_y, s.right_end_y, m.y, m.height)
+                {
+                    x2 = x2.min(right);
+                }
+                let top = screen.y as i64 + s.top as i64;
+                if s.top > 0
+                    && contains(top, m.y, m.height)
+                    && overlaps(s.top_start_x, s.top_end_x, m.x, m.width)
+                {
+                    y1 = y1.max(top);
+                }
+                let bottom = screen_bottom - s.bottom as i64;
+                if s.bottom > 0
+                    && contains(bottom, m.y + 1, m.height)
+                    && overlaps(s.bottom_start_x, s.bottom_end_x, m.x, m.width)
+                {
+                    y2 = y2.min(bottom);
+                }
+            }
+            XcbGeometry {
+                x: x1 as i32,
+                y: y1 as i32,
+                width: (x2 - x1).max(0) as u32,
+                height: (y2 - y1).max(0) as u32,
+            }
+        })
+        .collect()
+}
+
+/// Reads the _NET_WORKAREA property of the root window, one area per desktop.
+pub unsafe fn get_net_workarea(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<Vec<XcbGeometry>, XcbGetPropertyError> {
+    let data = get_u32_property(
+        xcb,
+        errors,
+        root,
+        atoms._NET_WORKAREA,
+        XCB_ATOM_CARDINAL,
+        ROOT_LIST_LEN,
+    )?;
+    Ok(data
+        .chunks_exact(4)
+        .map(|a| XcbGeometry {
+            x: a[0] as i32,
+            y: a[1] as i32,
+            width: a[2],
+            height: a[3],
+        })
+        .collect())
+}
+
+/// Sets the _NET_WORKAREA property of the root window, one area per desktop.
+pub unsafe fn set_net_workarea(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    areas: &[XcbGeometry],
+) -> XcbPendingCommands {
+    let data: Vec<u32> = areas
+        .iter()
+        .flat_map(|a| [a.x as u32, a.y as u32, a.width, a.height])
+        .collect();
+    set_property(xcb, c, root, atoms._NET_WORKAREA, XCB_ATOM_CARDINAL, &data)
+}
+
+/// The capabilities of the running EWMH-compliant window manager.
+#[derive(Clone, Debug)]
+pub struct XcbWmCapabilities {
+    /// The child window used for the _NET_SUPPORTING_WM_CHECK handshake.
+    pub check_window: xcb_window_t,
+    /// The _NET_WM_NAME of the check window.
+    pub name: Option<String>,
+    supported: HashSet<xcb_atom_t>,
+}
+
+impl XcbWmCapabilities {
+    /// Returns whether the atom is listed in _NET_SUPPORTED.
+    pub fn supports(&self, atom: xcb_atom_t) -> bool {
+        self.supported.contains(&atom)
+    }
+
+    pub fn supported(&self) -> &HashSet<xcb_atom_t> {
+        &self.supported
+    }
+}
+
+/// Detects an EWMH-compliant window manager.
+///
+/// Returns `None` if no window manager is running or if _NET_SUPPORTING_WM_CHECK is stale,
+/// that is, if the check window does not exist or does not refer to itself.
+pub unsafe fn probe_wm(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<Option<XcbWmCapabilities>, XcbGetPropertyError> {
+    let check = |window| {
+        request_property::<u32>(
+            xcb,
+            errors.c,
+            window,
+            atoms._NET_SUPPORTING_WM_CHECK,
+            XCB_ATOM_WINDOW,
+            false,
+            1,
+        )
+    };
+    let root_check = check(root);
+    let supported = request_property::<u32>(
+        xcb,
+        errors.c,
+        root,
+        atoms._NET_SUPPORTED,
+        XCB_ATOM_ATOM,
+        false,
+        256,
+    );
+    let root_check = root_check.reply_limited(xcb, errors, truncate_after(1));
+    let supported = supported.reply_limited(xcb, errors, truncate_after(ROOT_LIST_LEN));
+    let check_window = match root_check {
+        Ok(w) if !w.value.is_empty() => w.value[0],
+        Err(XcbGetPropertyError::Xcb(e)) => return Err(e.into()),
+        _ => return Ok(None),
+    };
+    let supported = match supported {
+        Ok(s) => s.value,
+        Err(XcbGetPropertyError::Xcb(e)) => return Err(e.into()),
+        Err(_) => vec![],
+    };
+    let child_check = check(check_window);
+    let name = request_property::<u8>(
+        xcb,
+        errors.c,
+        check_window,
+        atoms._NET_WM_NAME,
+        atoms.UTF8_STRING,
+        false,
+        256,
+    );
+    let name_limit = XcbPropertyLimit {
+        max_bytes: WM_NAME_LIMIT,
+        policy: XcbPropertyLimitPolicy::Truncate,
+    };
+    let child_check = child_check.reply_limited(xcb, errors, truncate_after(1));
+    let name = name.reply_limited(xcb, errors, name_limit);
+    match child_check {
+        Ok(w) if w.value.first() == Some(&check_window) => {}
+        Err(XcbGetPropertyError::Xcb(e)) if e.error_code != XCB_WINDOW => return Err(e.into()),
+        _ => return Ok(None),
+    }
+    let name = match name {
+        Ok(n) => Some(XcbTextEncoding::Utf8String.decode(&n.value)),
+        Err(XcbGetPropertyError::Xcb(e)) if e.error_code != XCB_WINDOW => return Err(e.into()),
+        Err(_) => None,
+    };
+    Ok(Some(XcbWmCapabilities {
+        check_window,
+        name,
+        supported: supported.into_iter().collect(),
+    }))
+}
+
+/// Creates the _NET_SUPPORTING_WM_CHECK window and announces the window manager.
+///
+/// The window is an unmapped InputOnly child of the root window. Returns the window.
+pub unsafe fn publish_wm(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    name: &str,
+    supported: &[xcb_atom_t],
+) -> (xcb_window_t, XcbPendingCommands) {
+    let window = xcb.xcb_generate_id(c);
+    let override_redirect = 1u32;
+    let mut commands = XcbPendingCommands::new();
+    commands.push(XcbPendingCommand::new(xcb.xcb_create_window_checked(
+        c,
+        0,
+        window,
+        root,
+        -1,
+        -1,
+        1,
+        1,
+        0,
+        XCB_WINDOW_CLASS_INPUT_ONLY as u16,
+        XCB_COPY_FROM_PARENT,
+        XCB_CW_OVERRIDE_REDIRECT,
+        &override_redirect as *const u32 as *const _,
+    )));
+    commands.extend(set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_SUPPORTING_WM_CHECK,
+        XCB_ATOM_WINDOW,
+        &[window],
+    ));
+    commands.extend(set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_WM_NAME,
+        atoms.UTF8_STRING,
+        name.as_bytes(),
+    ));
+    commands.extend(set_net_supported(xcb, c, atoms, root, supported));
+    // Clients consider the window manager present as soon as this property is set on the
+    // root window, so it is set last.
+    commands.extend(set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_SUPPORTING_WM_CHECK,
+        XCB_ATOM_WINDOW,
+        &[window],
+    ));
+    (window, commands)
+}
+
+pub unsafe fn set_net_supported(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    supported: &[xcb_atom_t],
+) -> XcbPendingCommands {
+    set_property(xcb, c, root, atoms._NET_SUPPORTED, XCB_ATOM_ATOM, supported)
+}
+
+/// Reads _NET_CLIENT_LIST, the managed windows in initial mapping order.
+pub unsafe fn get_net_client_list(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<Vec<xcb_window_t>, XcbGetPropertyError> {
+    get_u32_property(
+        xcb,
+        errors,
+        root,
+        atoms._NET_CLIENT_LIST,
+        XCB_ATOM_WINDOW,
+        ROOT_LIST_LEN,
+    )
+}
+
+pub unsafe fn set_net_client_list(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    windows: &[xcb_window_t],
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_CLIENT_LIST,
+        XCB_ATOM_WINDOW,
+        windows,
+    )
+}
+
+/// Reads _NET_CLIENT_LIST_STACKING, the managed windows from bottom to top.
+pub unsafe fn get_net_client_list_stacking(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<Vec<xcb_window_t>, XcbGetPropertyError> {
+    get_u32_property(
+        xcb,
+        errors,
+        root,
+        atoms._NET_CLIENT_LIST_STACKING,
+        XCB_ATOM_WINDOW,
+        ROOT_LIST_LEN,
+    )
+}
+
+pub unsafe fn set_net_client_list_stacking(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    windows: &[xcb_window_t],
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_CLIENT_LIST_STACKING,
+        XCB_ATOM_WINDOW,
+        windows,
+    )
+}
+
+pub unsafe fn get_net_number_of_desktops(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<u32, XcbGetPropertyError> {
+    get_u32_value(
+        xcb,
+        errors,
+        root,
+        atoms._NET_NUMBER_OF_DESKTOPS,
+        XCB_ATOM_CARDINAL,
+    )
+}
+
+pub unsafe fn set_net_number_of_desktops(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    number: u32,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_NUMBER_OF_DESKTOPS,
+        XCB_ATOM_CARDINAL,
+        &[number],
+    )
+}
+
+/// Reads _NET_DESKTOP_NAMES. The list can be shorter or longer than the number of
+/// desktops.
+pub unsafe fn get_net_desktop_names(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<Vec<String>, XcbGetPropertyError> {
+    let limit = XcbPropertyLimit {
+        max_bytes: DESKTOP_NAMES_LIMIT,
+        policy: XcbPropertyLimitPolicy::Truncate,
+    };
+    let value = get_property_limited::<u8>(
+        xcb,
+        errors,
+        root,
+        atoms._NET_DESKTOP_NAMES,
+        atoms.UTF8_STRING,
+        false,
+        256,
+        limit,
+    )?;
+    Ok(split_strings(&value.value)
+        .into_iter()
+        .map(|s| XcbTextEncoding::Utf8String.decode(s))
+        .collect())
+}
+
+pub unsafe fn set_net_desktop_names<S: AsRef<str>>(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    names: &[S],
+) -> XcbPendingCommands {
+    let names: Vec<_> = names.iter().map(|n| n.as_ref().as_bytes()).collect();
+    let value = join_strings(&names);
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_DESKTOP_NAMES,
+        atoms.UTF8_STRING,
+        &value,
+    )
+}
+
+pub unsafe fn get_net_current_desktop(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<u32, XcbGetPropertyError> {
+    get_u32_value(
+        xcb,
+        errors,
+        root,
+        atoms._NET_CURRENT_DESKTOP,
+        XCB_ATOM_CARDINAL,
+    )
+}
+
+pub unsafe fn set_net_current_desktop(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    desktop: u32,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_CURRENT_DESKTOP,
+        XCB_ATOM_CARDINAL,
+        &[desktop],
+    )
+}
+
+/// Reads _NET_DESKTOP_VIEWPORT, the top-left corner of the viewport of each desktop.
+pub unsafe fn get_net_desktop_viewport(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<Vec<(u32, u32)>, XcbGetPropertyError> {
+    let value = get_u32_property(
+        xcb,
+        errors,
+        root,
+        atoms._NET_DESKTOP_VIEWPORT,
+        XCB_ATOM_CARDINAL,
+        ROOT_LIST_LEN,
+    )?;
+    Ok(value.chunks_exact(2).map(|v| (v[0], v[1])).collect())
+}
+
+pub unsafe fn set_net_desktop_viewport(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    viewports: &[(u32, u32)],
+) -> XcbPendingCommands {
+    let value: Vec<u32> = viewports.iter().flat_map(|&(x, y)| [x, y]).collect();
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_DESKTOP_VIEWPORT,
+        XCB_ATOM_CARDINAL,
+        &value,
+    )
+}
+
+/// Reads _NET_DESKTOP_GEOMETRY, the width and height of the desktops.
+pub unsafe fn get_net_desktop_geometry(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<(u32, u32), XcbGetPropertyError> {
+    let value = get_u32_property(
+        xcb,
+        errors,
+        root,
+        atoms._NET_DESKTOP_GEOMETRY,
+        XCB_ATOM_CARDINAL,
+        2,
+    )?;
+    match value[..] {
+        [width, height, ..] => Ok((width, height)),
+        _ => Err(XcbGetPropertyError::Unset),
+    }
+}
+
+pub unsafe fn set_net_desktop_geometry(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    width: u32,
+    height: u32,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_DESKTOP_GEOMETRY,
+        XCB_ATOM_CARDINAL,
+        &[width, height],
+    )
+}
+
+/// Reads _NET_ACTIVE_WINDOW. Returns `XCB_NONE` if no window is active.
+pub unsafe fn get_net_active_window(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<xcb_window_t, XcbGetPropertyError> {
+    get_u32_value(xcb, errors, root, atoms._NET_ACTIVE_WINDOW, XCB_ATOM_WINDOW)
+}
+
+pub unsafe fn set_net_active_window(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    window: xcb_window_t,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_ACTIVE_WINDOW,
+        XCB_ATOM_WINDOW,
+        &[window],
+    )
+}
+
+pub unsafe fn get_net_showing_desktop(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+) -> Result<bool, XcbGetPropertyError> {
+    let value = get_u32_value(
+        xcb,
+        errors,
+        root,
+        atoms._NET_SHOWING_DESKTOP,
+        XCB_ATOM_CARDINAL,
+    )?;
+    Ok(value != 0)
+}
+
+pub unsafe fn set_net_showing_desktop(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    showing: bool,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        root,
+        atoms._NET_SHOWING_DESKTOP,
+        XCB_ATOM_CARDINAL,
+        &[showing as u32],
+    )
+}
+
+/// Reads _NET_WM_DESKTOP, the desktop of a window. `ALL_DESKTOPS` means that the window is
+/// shown on all desktops.
+pub unsafe fn get_net_wm_desktop(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> Result<u32, XcbGetPropertyError> {
+    get_u32_value(
+        xcb,
+        errors,
+        window,
+        atoms._NET_WM_DESKTOP,
+        XCB_ATOM_CARDINAL,
+    )
+}
+
+pub unsafe fn set_net_wm_desktop(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+    desktop: u32,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_WM_DESKTOP,
+        XCB_ATOM_CARDINAL,
+        &[desktop],
+    )
+}
+
+/// Asks the window manager to switch to another desktop.
+pub unsafe fn request_current_desktop(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    desktop: u32,
+    timestamp: xcb_timestamp_t,
+) -> XcbPendingCommand {
+    let data = [desktop, timestamp, 0, 0, 0];
+    send_root_message(xcb, c, root, root, atoms._NET_CURRENT_DESKTOP, data)
+}
+
+/// A _NET_ACTIVE_WINDOW request.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct XcbActiveWindowRequest {
+    pub window: xcb_window_t,
+    pub source: XcbSourceIndication,
+    pub timestamp: xcb_timestamp_t,
+    /// The active window of the requesting client or `XCB_NONE`.
+    pub current: xcb_window_t,
+}
+
+impl XcbActiveWindowRequest {
+    /// Decodes a client message. Returns `None` if it is not a _NET_ACTIVE_WINDOW message.
+    pub fn decode(atoms: &XcbEwmhAtoms, event: &xcb_client_message_event_t) -> Option<Self> {
+        if event.type_ != atoms._NET_ACTIVE_WINDOW || event.format != 32 {
+            return None;
+        }
+        let data = unsafe { event.data.data32 };
+        Some(Self {
+            window: event.window,
+            source: data[0].into(),
+            timestamp: data[1],
+            current: data[2],
+        })
+    }
+}
+
+/// Asks the window manager to activate a window.
+pub unsafe fn request_active_window(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    request: &XcbActiveWindowRequest,
+) -> XcbPendingCommand {
+    let data = [
+        request.source.into(),
+        request.timestamp,
+        request.current,
+        0,
+        0,
+    ];
+    send_root_message(xcb, c, root, request.window, atoms._NET_ACTIVE_WINDOW, data)
+}
+
+/// Asks the window manager to close a window.
+pub unsafe fn request_close_window(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    window: xcb_window_t,
+    source: XcbSourceIndication,
+    timestamp: xcb_timestamp_t,
+) -> XcbPendingCommand {
+    let data = [timestamp, source.into(), 0, 0, 0];
+    send_root_message(xcb, c, root, window, atoms._NET_CLOSE_WINDOW, data)
+}
+
+/// Asks the window manager to move a window to another desktop.
+pub unsafe fn request_wm_desktop(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    window: xcb_window_t,
+    desktop: u32,
+    source: XcbSourceIndication,
+) -> XcbPendingCommand {
+    let data = [desktop, source.into(), 0, 0, 0];
+    send_root_message(xcb, c, root, window, atoms._NET_WM_DESKTOP, data)
+}
+
+/// The direction of a _NET_WM_MOVERESIZE operation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbMoveResizeDirection {
+    SizeTopLeft,
+    SizeTop,
+    SizeTopRight,
+    SizeRight,
+    SizeBottomRight,
+    SizeBottom,
+    SizeBottomLeft,
+    SizeLeft,
+    Move,
+    SizeKeyboard,
+    MoveKeyboard,
+    Cancel,
+}
+
+impl TryFrom<u32> for XcbMoveResizeDirection {
+    type Error = u32;
+
+    fn try_from(v: u32) -> Result<Self, Self::Error> {
+        use XcbMoveResizeDirection::*;
+        let d = match v {
+            0 => SizeTopLeft,
+            1 => SizeTop,
+            2 => SizeTopRight,
+            3 => SizeRight,
+            4 => SizeBottomRight,
+            5 => SizeBottom,
+            6 => SizeBottomLeft,
+            7 => SizeLeft,
+            8 => Move,
+            9 => SizeKeyboard,
+            10 => MoveKeyboard,
+            11 => Cancel,
+            _ => return Err(v),
+        };
+        Ok(d)
+    }
+}
+
+impl From<XcbMoveResizeDirection> for u32 {
+    fn from(d: XcbMoveResizeDirection) -> Self {
+        d as u32
+    }
+}
+
+/// A _NET_WM_MOVERESIZE request.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct XcbMoveResizeRequest {
+    pub window: xcb_window_t,
+    /// The position of the pointer relative to the root window.
+    pub x_root: i32,
+    pub y_root: i32,
+    pub direction: XcbMoveResizeDirection,
+    /// The button that started the operation or 0 for keyboard-initiated operations.
+    pub button: u32,
+    pub source: XcbSourceIndication,
+}
+
+impl XcbMoveResizeRequest {
+    /// Decodes a client message. Returns `None` if it is not a valid _NET_WM_MOVERESIZE
+    /// message.
+    pub fn decode(atoms: &XcbEwmhAtoms, event: &xcb_client_message_event_t) -> Option<Self> {
+        if event.type_ != atoms._NET_WM_MOVERESIZE || event.format != 32 {
+            return None;
+        }
+        let data = unsafe { event.data.data32 };
+        Some(Self {
+            window: event.window,
+            x_root: data[0] as i32,
+            y_root: data[1] as i32,
+            direction: XcbMoveResizeDirection::try_from(data[2]).ok()?,
+            button: data[3],
+            source: data[4].into(),
+        })
+    }
+}
+
+/// Asks the window manager to start or cancel an interactive move or resize.
+///
+/// The pointer is ungrabbed first so that the window manager can grab it.
+pub unsafe fn request_moveresize(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    request: &XcbMoveResizeRequest,
+) -> XcbPendingCommands {
+    let mut commands = XcbPendingCommands::new();
+    commands.push(xcb.xcb_ungrab_pointer_checked(c, XCB_CURRENT_TIME).into());
+    let data = [
+        request.x_root as u32,
+        request.y_root as u32,
+        request.direction.into(),
+        request.button,
+        request.source.into(),
+    ];
+    commands.push(send_root_message(
+        xcb,
+        c,
+        root,
+        request.window,
+        atoms._NET_WM_MOVERESIZE,
+        data,
+    ));
+    commands
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbGetFrameExtentsError {
+    #[error("Could not read the property: {0}")]
+    Property(#[from] XcbGetPropertyError),
+    #[error("The property is invalid: {0}")]
+    FrameExtents(#[from] XcbPropertyStructError),
+}
+
+impl From<XcbError> for XcbGetFrameExtentsError {
+    fn from(e: XcbError) -> Self {
+        XcbGetFrameExtentsError::Property(e.into())
+    }
+}
+
+unsafe fn get_frame_extents(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+) -> Result<XcbFrameExtents, XcbGetFrameExtentsError> {
+    let value = get_u32_property(
+        xcb,
+        errors,
+        window,
+        property,
+        XCB_ATOM_CARDINAL,
+        XcbFrameExtents::LEN,
+    )?;
+    Ok(XcbFrameExtents::from_elements(&value)?)
+}
+
+/// Reads _NET_FRAME_EXTENTS, the size of the frame that the window manager added.
+pub unsafe fn get_net_frame_extents(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> Result<XcbFrameExtents, XcbGetFrameExtentsError> {
+    get_frame_extents(xcb, errors, window, atoms._NET_FRAME_EXTENTS)
+}
+
+pub unsafe fn set_net_frame_extents(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+    extents: &XcbFrameExtents,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_FRAME_EXTENTS,
+        XCB_ATOM_CARDINAL,
+        extents.as_elements(),
+    )
+}
+
+/// Reads _GTK_FRAME_EXTENTS, the size of the client-side decorations, such as shadows,
+/// that are drawn inside of the window.
+pub unsafe fn get_gtk_frame_extents(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+) -> Result<XcbFrameExtents, XcbGetFrameExtentsError> {
+    get_frame_extents(xcb, errors, window, atoms._GTK_FRAME_EXTENTS)
+}
+
+pub unsafe fn set_gtk_frame_extents(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbEwmhAtoms,
+    window: xcb_window_t,
+    extents: &XcbFrameExtents,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        atoms._GTK_FRAME_EXTENTS,
+        XCB_ATOM_CARDINAL,
+        extents.as_elements(),
+    )
+}
+
+/// Asks the window manager to estimate the frame extents of an unmapped window and waits
+/// for the answer.
+///
+/// The window must have selected PropertyChange events. All other events received while
+/// waiting are appended to `events` in order. Returns `None` if the window manager does
+/// not answer within `timeout`.
+pub unsafe fn request_frame_extents(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbEwmhAtoms,
+    root: xcb_window_t,
+    window: xcb_window_t,
+    timeout: Duration,
+    events: &mut Vec<XcbBox<xcb_generic_event_t>>,
+) -> Result<Option<XcbFrameExtents>, XcbGetFrameExtentsError> {
+    let deadline = Instant::now() + timeout;
+    let data = [0; 5];
+    send_root_message(
+        xcb,
+        errors.c,
+        root,
+        window,
+        atoms._NET_REQUEST_FRAME_EXTENTS,
+        data,
+    )
+    .check(xcb, errors)?;
+    loop {
+        let event = xcb.xcb_poll_for_event(errors.c);
+        if event.is_null() {
+            errors.check_connection(xcb)?;
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            let mut pfd = libc::pollfd {
+                fd: xcb.xcb_get_file_descriptor(errors.c),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ms = (deadline - now).as_millis().clamp(1, i32::MAX as u128);
+            libc::poll(&mut pfd, 1, ms as i32);
+            continue;
+        }
+        let event = XcbBox::new(event);
+        if event.response_type & 0x7f == XCB_PROPERTY_NOTIFY {
+            let notify = &*(&*event as *const _ as *const xcb_property_notify_event_t);
+            if notify.window == window
+                && notify.atom == atoms._NET_FRAME_EXTENTS
+                && notify.state == XCB_PROPERTY_NEW_VALUE as u8
+            {
+                return get_net_frame_extents(xcb, errors, atoms, window).map(Some);
+            }
+        }
+        events.push(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atoms() -> XcbEwmhAtoms {
+        // All fields are atoms. Those that are not set below are XCB_NONE.
+        let mut atoms: XcbEwmhAtoms = unsafe { std::mem::zeroed() };
+        atoms._NET_WM_STATE = 1000;
+        atoms._NET_WM_STATE_MAXIMIZED_VERT = 1001;
+        atoms._NET_WM_STATE_MAXIMIZED_HORZ = 1002;
+        atoms._NET_WM_STATE_FULLSCREEN = 1003;
+        atoms._NET_WM_STATE_ABOVE = 1004;
+        atoms._NET_WM_MOVERESIZE = 1005;
+        atoms
+    }
+
+    fn message(type_: xcb_atom_t, data32: [u32; 5]) -> xcb_client_message_event_t {
+        xcb_client_message_event_t {
+            response_type: XCB_CLIENT_MESSAGE,
+            format: 32,
+            window: 7,
+            type_,
+            data: xcb_client_message_data_t { data32 },
+            ..Default::default()
+        }
+    }
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32) -> XcbGeometry {
+        XcbGeometry {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    const SCREEN: XcbGeometry = XcbGeometry {
+        x: 0,
+        y: 0,
+        width: 3840,
+        height: 1080,
+    };
+
+    const MONITORS: [XcbGeometry; 2] = [
+        XcbGeometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        },
+        XcbGeometry {
+            x: 1920,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        },
+    ];
+
+    #[test]
+    fn panel_on_shared_edge() {
+        // A 30 pixel panel at the left edge of the right monitor.
+        let left = XcbStrutPartial {
+            left: 1950,
+            left_start_y: 0,
+            left_end_y: 1079,
+            ..Default::default()
+        };
+        // A 20 pixel panel at the right edge of the left monitor.
+        let right = XcbStrutPartial {
+            right: 1940,
+            right_start_y: 0,
+            right_end_y: 1079,
+            ..Default::default()
+        };
+        assert_eq!(
+            compute_workareas(SCREEN, &MONITORS, &[left, right]),
+            [geometry(0, 0, 1900, 1080), geometry(1950, 0, 1890, 1080)],
+        );
+    }
+
+    #[test]
+    fn partial_strut_on_one_monitor() {
+        let top = XcbStrutPartial {
+            top: 30,
+            top_start_x: 0,
+            top_end_x: 1919,
+            ..Default::default()
+        };
+        let bottom = XcbStrutPartial {
+            bottom: 40,
+            bottom_start_x: 1920,
+            bottom_end_x: 3839,
+            ..Default::default()
+        };
+        assert_eq!(
+            compute_workareas(SCREEN, &MONITORS, &[top, bottom]),
+            [geometry(0, 30, 1920, 1050), geometry(1920, 0, 1920, 1040)],
+        );
+    }
+
+    #[test]
+    fn legacy_strut_covers_whole_edge() {
+        let strut: XcbStrutPartial = XcbStrut {
+            left: 0,
+            right: 0,
+            top: 25,
+            bottom: 0,
+        }
+        .into();
+        assert_eq!(strut.top_start_x, 0);
+        assert_eq!(strut.top_end_x, u32::MAX);
+        assert_eq!(
+            compute_workareas(SCREEN, &MONITORS, &[strut]),
+            [geometry(0, 25, 1920, 1055), geometry(1920, 25, 1920, 1055)],
+        );
+    }
+
+    #[test]
+    fn strut_covering_whole_monitor_is_ignored() {
+        // These struts end exactly at the boundary between the monitors. Each covers one
+        // monitor completely, but its inner edge does not lie within that monitor, so the
+        // monitor is treated as the neighbor of a panel and keeps its workarea. For the
+        // other monitor, the inner edge is its outer edge and nothing is reserved.
+        let right = XcbStrutPartial {
+            right: 1920,
+            right_start_y: 0,
+            right_end_y: 1079,
+            ..Default::default()
+        };
+        let left = XcbStrutPartial {
+            left: 1920,
+            left_start_y: 0,
+            left_end_y: 1079,
+            ..Default::default()
+        };
+        assert_eq!(
+            compute_workareas(SCREEN, &MONITORS, &[right, left]),
+            MONITORS,
+        );
+    }
+
+    #[test]
+    fn net_wm_state_two_properties() {
+        let atoms = atoms();
+        let event = message(atoms._NET_WM_STATE, [1, 1001, 1002, 2, 0]);
+        let request = XcbNetWmStateRequest::decode(&atoms, &event).unwrap();
+        assert_eq!(
+            request,
+            XcbNetWmStateRequest {
+                window: 7,
+                action: XcbNetWmStateAction::Add,
+                states: XcbNetWmState::MAXIMIZED_VERT | XcbNetWmState::MAXIMIZED_HORZ,
+                source: XcbSourceIndication::Pager,
+            }
+        );
+        // An unknown or missing second property is ignored.
+        for second in [0, 4242] {
+            let event = message(atoms._NET_WM_STATE, [1, 1003, second, 1, 0]);
+            let request = XcbNetWmStateRequest::decode(&atoms, &event).unwrap();
+            assert_eq!(request.states, XcbNetWmState::FULLSCREEN);
+        }
+    }
+
+    #[test]
+    fn net_wm_state_actions() {
+        let atoms = atoms();
+        let state = XcbNetWmState::MAXIMIZED_VERT | XcbNetWmState::ABOVE;
+        let apply = |action: u32| {
+            let event = message(atoms._NET_WM_STATE, [action, 1001, 1002, 1, 0]);
+            XcbNetWmStateRequest::decode(&atoms, &event)
+                .unwrap()
+                .apply(state)
+        };
+        assert_eq!(apply(0), XcbNetWmState::ABOVE);
+        assert_eq!(
+            apply(1),
+            XcbNetWmState::MAXIMIZED_VERT | XcbNetWmState::MAXIMIZED_HORZ | XcbNetWmState::ABOVE
+        );
+        // Each state is toggled independently.
+        assert_eq!(
+            apply(2),
+            XcbNetWmState::MAXIMIZED_HORZ | XcbNetWmState::ABOVE
+        );
+    }
+
+    #[test]
+    fn net_wm_state_invalid() {
+        let atoms = atoms();
+        let event = message(atoms._NET_WM_STATE, [3, 1001, 0, 1, 0]);
+        assert_eq!(XcbNetWmStateRequest::decode(&atoms, &event), None);
+        let event = message(atoms._NET_WM_MOVERESIZE, [1, 1001, 0, 1, 0]);
+        assert_eq!(XcbNetWmStateRequest::decode(&atoms, &event), None);
+        let mut event = message(atoms._NET_WM_STATE, [1, 1001, 0, 1, 0]);
+        event.format = 8;
+        assert_eq!(XcbNetWmStateRequest::decode(&atoms, &event), None);
+        // Unknown sources are treated as legacy clients.
+        let event = message(atoms._NET_WM_STATE, [1, 1001, 0, 9, 0]);
+        let request = XcbNetWmStateRequest::decode(&atoms, &event).unwrap();
+        assert_eq!(request.source, XcbSourceIndication::Legacy);
+    }
+
+    #[test]
+    fn moveresize_decode() {
+        let atoms = atoms();
+        let event = message(atoms._NET_WM_MOVERESIZE, [-5i32 as u32, 300, 8, 1, 1]);
+        assert_eq!(
+            XcbMoveResizeRequest::decode(&atoms, &event),
+            Some(XcbMoveResizeRequest {
+                window: 7,
+                x_root: -5,
+                y_root: 300,
+                direction: XcbMoveResizeDirection::Move,
+                button: 1,
+                source: XcbSourceIndication::Application,
+            })
+        );
+        let event = message(atoms._NET_WM_MOVERESIZE, [0, 0, 11, 0, 2]);
+        let request = XcbMoveResizeRequest::decode(&atoms, &event).unwrap();
+        assert_eq!(request.direction, XcbMoveResizeDirection::Cancel);
+    }
+
+    #[test]
+    fn moveresize_invalid_direction() {
+        let atoms = atoms();
+        for direction in [12, u32::MAX] {
+            let event = message(atoms._NET_WM_MOVERESIZE, [0, 0, direction, 1, 1]);
+            assert_eq!(XcbMoveResizeRequest::decode(&atoms, &event), None);
+        }
+        let event = message(atoms._NET_WM_STATE, [0, 0, 8, 1, 1]);
+        assert_eq!(XcbMoveResizeRequest::decode(&atoms, &event), None);
+    }
+
+    fn icon(width: u32, height: u32, pixel: u32) -> XcbNetWmIcon {
+        XcbNetWmIcon {
+            width,
+            height,
+            pixels: vec![pixel; (width * height) as usize],
+        }
+    }
+
+    #[test]
+    fn icon_truncated() {
+        let mut data = XcbNetWmIcon::encode(&[icon(2, 2, 1), icon(3, 3, 2)]).unwrap();
+        assert_eq!(XcbNetWmIcon::decode(&data).len(), 2);
+        data.pop();
+        assert_eq!(XcbNetWmIcon::decode(&data), [icon(2, 2, 1)]);
+        data.truncate(7);
+        assert_eq!(XcbNetWmIcon::decode(&data), [icon(2, 2, 1)]);
+    }
+
+    #[test]
+    fn icon_oversized() {
+        let mut data = XcbNetWmIcon::encode(&[icon(1, 1, 1)]).unwrap();
+        data.extend_from_slice(&[u32::MAX, u32::MAX, 0, 0]);
+        assert_eq!(XcbNetWmIcon::decode(&data), [icon(1, 1, 1)]);
+    }
+
+    #[test]
+    fn icon_zero_dimension() {
+        let data = [0, 5, 4, 0, 1, 1, 3];
+        assert_eq!(XcbNetWmIcon::decode(&data), [icon(1, 1, 3)]);
+        let empty = icon(0, 4, 0);
+        assert!(matches!(
+            XcbNetWmIcon::encode(&[icon(1, 1, 1), empty.clone()]),
+            Err(XcbNetWmIconError::InvalidSize { index: 1, .. })
+        ));
+        assert_eq!(empty.scale(4, 4), None);
+        assert_eq!(icon(2, 2, 1).scale(0, 4), None);
+        assert_eq!(XcbNetWmIcon::select_scaled(&[empty], 16), None);
+        assert_eq!(XcbNetWmIcon::from_rgba(0, 1, &[]), None);
+    }
+
+    #[test]
+    fn icon_wrong_pixel_count() {
+        let mut bad = icon(2, 2, 1);
+        bad.pixels.pop();
+        assert!(matches!(
+            XcbNetWmIcon::encode(&[bad.clone()]),
+            Err(XcbNetWmIconError::WrongPixelCount { len: 3, .. })
+        ));
+        assert_eq!(bad.scale(4, 4), None);
+        assert_eq!(XcbNetWmIcon::from_rgba(2, 2, &[0; 12]), None);
+    }
+
+    #[test]
+    fn icon_rgba() {
+        let rgba = [1, 2, 3, 4, 5, 6, 7, 8];
+        let icon = XcbNetWmIcon::from_rgba(2, 1, &rgba).unwrap();
+        assert_eq!(icon.pixels, [0x04010203, 0x08050607]);
+        assert_eq!(icon.to_rgba(), rgba);
+    }
+
+    #[test]
+    fn icon_select() {
+        let icons = [icon(16, 16, 0), icon(32, 24, 0), icon(48, 48, 0)];
+        assert_eq!(XcbNetWmIcon::select(&icons, 20), Some(&icons[0]));
+        assert_eq!(XcbNetWmIcon::select(&icons, 24), Some(&icons[0]));
+        assert_eq!(XcbNetWmIcon::select(&icons, 30), Some(&icons[1]));
+        assert_eq!(XcbNetWmIcon::select(&icons, 1000), Some(&icons[2]));
+        assert_eq!(XcbNetWmIcon::select(&[], 16), None);
+        let scaled = XcbNetWmIcon::select_scaled(&icons, 64).unwrap();
+        assert_eq!((scaled.width, scaled.height), (64, 64));
+        let scaled = XcbNetWmIcon::select_scaled(&icons, 24).unwrap();
+        assert_eq!((scaled.width, scaled.height), (24, 24));
+    }
+
+    #[test]
+    fn icon_scale_round_trip() {
+        let original = XcbNetWmIcon {
+            width: 3,
+            height: 2,
+            pixels: vec![
+                0xff102030, 0x80405060, 0x00000000, //
+                0xffffffff, 0x40ff0000, 0xff000000,
+            ],
+        };
+        let large = original.scale(6, 4).unwrap();
+        assert_eq!(
+            large.pixels[..6],
+            [0xff102030, 0xff102030, 0x80405060, 0x80405060, 0x00000000, 0x00000000]
+        );
+        assert_eq!(large.scale(3, 2).unwrap(), original);
+        // Transparent pixels do not change the color.
+        let half = XcbNetWmIcon {
+            width: 2,
+            height: 1,
+            pixels: vec![0xff0000ff, 0x00ff0000],
+        };
+        assert_eq!(half.scale(1, 1).unwrap().pixels, [0x7f0000ff]);
+    }
+}
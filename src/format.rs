@@ -1,4 +1,5 @@
-use std::mem;
+use std::{mem, ptr, slice};
+use thiserror::Error;
 
 /// A type that can be sent via client messages or be stored in properties.
 ///
@@ -24,3 +25,139 @@ imp!(u32);
 imp!(i8);
 imp!(i16);
 imp!(i32);
+
+/// A struct that is stored in a property as an array of `Element`s.
+///
+/// # Safety
+///
+/// This must not be implemented manually. Use `xcb_property_struct!` instead.
+pub unsafe trait XcbPropertyStruct: Copy + Default {
+    type Element: XcbDataType;
+
+    /// The number of elements in the shortest valid encoding.
+    const MIN_LEN: usize;
+
+    /// The number of elements in the full encoding.
+    const LEN: usize = mem::size_of::<Self>() / mem::size_of::<Self::Element>();
+
+    fn as_elements(&self) -> &[Self::Element] {
+        unsafe { slice::from_raw_parts(self as *const _ as *const Self::Element, Self::LEN) }
+    }
+
+    /// Decodes the struct. Optional fields missing from `value` are set to their default
+    /// values.
+    fn from_elements(value: &[Self::Element]) -> Result<Self, XcbPropertyStructError> {
+        if value.len() < Self::MIN_LEN || value.len() > Self::LEN {
+            return Err(XcbPropertyStructError::WrongSize {
+                min: Self::MIN_LEN,
+                max: Self::LEN,
+                actual: value.len(),
+            });
+        }
+        let mut res = Self::default();
+        unsafe {
+            ptr::copy_nonoverlapping(
+                value.as_ptr(),
+                &mut res as *mut Self as *mut Self::Element,
+                value.len(),
+            );
+        }
+        Ok(res)
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbPropertyStructError {
+    #[error("The data has the wrong size (expected: {min} to {max} elements, actual: {actual})")]
+    WrongSize {
+        min: usize,
+        max: usize,
+        actual: usize,
+    },
+}
+
+/// Defines a `#[repr(C)]` struct that is stored in a property as an array of elements.
+///
+/// Every field must implement `XcbDataType`. The layout is checked at compile time. Fields
+/// after `..` are optional: shorter encodings that lack them are accepted and the missing
+/// fields are set to their default values.
+///
+/// ```
+/// xcb_dl_util::xcb_property_struct! {
+///     #[derive(Copy, Clone, Debug, Default)]
+///     pub struct XembedInfo: [u32] {
+///         pub version: u32,
+///         ..
+///         pub flags: u32,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! xcb_property_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident: [$elem:ty] {
+            $(
+                $(#[$fmeta:meta])*
+                pub $field:ident: $fty:ty,
+            )*
+            $(
+                ..
+                $(
+                    $(#[$ometa:meta])*
+                    pub $ofield:ident: $oty:ty,
+                )*
+            )?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        $vis struct $name {
+            $(
+                $(#[$fmeta])*
+                pub $field: $fty,
+            )*
+            $($(
+                $(#[$ometa])*
+                pub $ofield: $oty,
+            )*)?
+        }
+
+        const _: () = {
+            fn assert_data_type<T: $crate::format::XcbDataType>() {}
+            #[allow(dead_code)]
+            fn assert_fields() {
+                $(assert_data_type::<$fty>();)*
+                $($(assert_data_type::<$oty>();)*)?
+            }
+            let min_size = 0 $(+ ::std::mem::size_of::<$fty>())*;
+            let size = min_size $($(+ ::std::mem::size_of::<$oty>())*)?;
+            let elem = ::std::mem::size_of::<$elem>();
+            assert!(size == ::std::mem::size_of::<$name>(), "the struct contains padding");
+            assert!(size % elem == 0, "the struct size is not a multiple of the element size");
+            assert!(
+                min_size % elem == 0,
+                "the required fields do not end on an element boundary"
+            );
+            assert!(
+                ::std::mem::align_of::<$name>() == ::std::mem::align_of::<$elem>(),
+                "the struct alignment differs from the element alignment"
+            );
+        };
+
+        unsafe impl $crate::format::XcbPropertyStruct for $name {
+            type Element = $elem;
+
+            const MIN_LEN: usize =
+                (0 $(+ ::std::mem::size_of::<$fty>())*) / ::std::mem::size_of::<$elem>();
+        }
+
+        impl<'a> ::std::convert::TryFrom<&'a [$elem]> for $name {
+            type Error = $crate::format::XcbPropertyStructError;
+
+            fn try_from(value: &'a [$elem]) -> Result<Self, Self::Error> {
+                $crate::format::XcbPropertyStruct::from_elements(value)
+            }
+        }
+    };
+}
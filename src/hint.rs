@@ -1,7 +1,17 @@
+use crate::error::XcbErrorParser;
+use crate::property::{
+    get_property_limited, get_u32_property, get_u32_value, request_any_property, request_property,
+    set_property, XcbAnyProperty, XcbGetPropertyError, XcbPropertyLimit, XcbPropertyLimitPolicy,
+    XcbPropertyValue,
+};
+use crate::text::{join_strings, split_strings, XcbTextProperty};
+use crate::void::XcbPendingCommands;
+use bstr::BString;
 use std::convert::TryFrom;
 use std::{mem, ptr};
 use thiserror::Error;
 use xcb_dl::ffi::*;
+use xcb_dl::Xcb;
 
 bitflags::bitflags! {
     #[derive(Default)]
@@ -46,6 +56,128 @@ impl Into<u32> for XcbGravity {
     }
 }
 
+/// The position and size of a window.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct XcbGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+crate::xcb_property_struct! {
+    /// The size of the decorations that a window manager adds around a window.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+    pub struct XcbFrameExtents: [u32] {
+        pub left: u32,
+        pub right: u32,
+        pub top: u32,
+        pub bottom: u32,
+    }
+}
+
+#[derive(Copy, Clone)]
+enum GravityAxis {
+    Start,
+    Center,
+    End,
+}
+
+impl XcbGravity {
+    fn axes(self) -> Option<(GravityAxis, GravityAxis)> {
+        use GravityAxis::*;
+        let axes = match self {
+            XcbGravity::NORTH => (Center, Start),
+            XcbGravity::NORTH_EAST => (End, Start),
+            XcbGravity::WEST => (Start, Center),
+            XcbGravity::CENTER => (Center, Center),
+            XcbGravity::EAST => (End, Center),
+            XcbGravity::SOUTH_WEST => (Start, End),
+            XcbGravity::SOUTH => (Center, End),
+            XcbGravity::SOUTH_EAST => (End, End),
+            XcbGravity::STATIC => return None,
+            // WIN_UNMAP positions the window like NORTH_WEST.
+            _ => (Start, Start),
+        };
+        Some(axes)
+    }
+
+    /// Returns the position of the reference point relative to the top-left corner of a
+    /// rectangle with the given outer size.
+    ///
+    /// The reference point of `STATIC` is the top-left corner of the window inside its
+    /// border and does not depend on the size. This function returns `(0, 0)` for it.
+    pub fn reference_offset(self, width: u32, height: u32) -> (i32, i32) {
+        let offset = |axis, len: u32| match axis {
+            GravityAxis::Start => 0,
+            GravityAxis::Center => len as i32 / 2,
+            GravityAxis::End => len as i32,
+        };
+        match self.axes() {
+            Some((x, y)) => (offset(x, width), offset(y, height)),
+            None => (0, 0),
+        }
+    }
+
+    /// Returns the amount by which the window must be moved when its border is replaced
+    /// by a frame, such that the reference point stays in place.
+    fn frame_offset(self, border_width: u32, extents: &XcbFrameExtents) -> (i32, i32) {
+        let bw = border_width as i32;
+        let offset = |axis, start: u32, end: u32| {
+            let diff = 2 * bw - start as i32 - end as i32;
+            match axis {
+                GravityAxis::Start => 0,
+                GravityAxis::Center => diff / 2,
+                GravityAxis::End => diff,
+            }
+        };
+        match self.axes() {
+            Some((x, y)) => (
+                offset(x, extents.left, extents.right),
+                offset(y, extents.top, extents.bottom),
+            ),
+            // The client area stays in place.
+            None => (bw - extents.left as i32, bw - extents.top as i32),
+        }
+    }
+
+    /// Translates the geometry requested by a client into the geometry of its frame.
+    ///
+    /// The position of the client geometry is the position of the top-left corner of its
+    /// border and the size is the size inside the border. The frame geometry includes
+    /// the frame extents.
+    pub fn client_to_frame(
+        self,
+        client: XcbGeometry,
+        border_width: u32,
+        extents: &XcbFrameExtents,
+    ) -> XcbGeometry {
+        let (dx, dy) = self.frame_offset(border_width, extents);
+        XcbGeometry {
+            x: client.x + dx,
+            y: client.y + dy,
+            width: client.width + extents.left + extents.right,
+            height: client.height + extents.top + extents.bottom,
+        }
+    }
+
+    /// The inverse of `client_to_frame`.
+    pub fn frame_to_client(
+        self,
+        frame: XcbGeometry,
+        border_width: u32,
+        extents: &XcbFrameExtents,
+    ) -> XcbGeometry {
+        let (dx, dy) = self.frame_offset(border_width, extents);
+        XcbGeometry {
+            x: frame.x - dx,
+            y: frame.y - dy,
+            width: frame.width.saturating_sub(extents.left + extents.right),
+            height: frame.height.saturating_sub(extents.top + extents.bottom),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 #[repr(C)]
 pub struct XcbAspect {
@@ -75,6 +207,8 @@ pub struct XcbSizeHints {
 }
 
 const SIZE_HINTS_LEN: usize = mem::size_of::<XcbSizeHints>() / 4;
+/// The length of the pre-ICCCM layout which lacks the base size and the window gravity.
+const OLD_SIZE_HINTS_LEN: usize = 15;
 
 // Compile time checks
 const _SIZE_HINTS_REM: [usize; mem::size_of::<XcbSizeHints>() % 4] = [];
@@ -172,23 +306,216 @@ impl XcbSizeHints {
     );
 }
 
+impl XcbSizeHints {
+    /// Returns the base size. Falls back to the minimum size as required by the ICCCM.
+    fn effective_base_size(&self) -> (u32, u32) {
+        self.base_size()
+            .or_else(|| self.min_size())
+            .unwrap_or((0, 0))
+    }
+
+    /// Returns the minimum size. Falls back to the base size as required by the ICCCM.
+    fn effective_min_size(&self) -> (u32, u32) {
+        self.min_size()
+            .or_else(|| self.base_size())
+            .unwrap_or((0, 0))
+    }
+
+    /// Returns the maximum size. A maximum of 0 is treated as unlimited.
+    fn effective_max_size(&self) -> (u32, u32) {
+        let unlimited = |v: u32| if v == 0 { u32::MAX } else { v };
+        self.max_size()
+            .map(|(w, h)| (unlimited(w), unlimited(h)))
+            .unwrap_or((u32::MAX, u32::MAX))
+    }
+
+    fn effective_resize_increments(&self) -> (u32, u32) {
+        self.resize_increments()
+            .map(|(w, h)| (w.max(1), h.max(1)))
+            .unwrap_or((1, 1))
+    }
+
+    /// Constrains a proposed size to one that satisfies the hints.
+    ///
+    /// The minimum and maximum size are applied first, then the aspect ratios and then the
+    /// resize increments. If the hints contradict each other, the minimum size takes
+    /// precedence.
+    pub fn constrain(&self, width: u32, height: u32) -> (u32, u32) {
+        let (min_w, min_h) = self.effective_min_size();
+        let (max_w, max_h) = self.effective_max_size();
+        let mut w = width.min(max_w).max(min_w);
+        let mut h = height.min(max_h).max(min_h);
+
+        if let Some((min_aspect, max_aspect)) = self.aspect_ratios() {
+            // The base size is only subtracted if it has been provided explicitly.
+            let (base_w, base_h) = self.base_size().unwrap_or((0, 0));
+            let aw = w.saturating_sub(base_w) as u64;
+            let ah = h.saturating_sub(base_h) as u64;
+            let (mut aw2, mut ah2) = (aw, ah);
+            if min_aspect.y > 0 && aw * (min_aspect.y as u64) < (min_aspect.x as u64) * ah {
+                // Too tall.
+                ah2 = aw * min_aspect.y as u64 / min_aspect.x as u64;
+            }
+            if max_aspect.y > 0 && aw * (max_aspect.y as u64) > (max_aspect.x as u64) * ah {
+                // Too wide.
+                aw2 = ah * max_aspect.x as u64 / max_aspect.y as u64;
+            }
+            if aw2 != aw {
+                w = base_w + aw2 as u32;
+            }
+            if ah2 != ah {
+                h = base_h + ah2 as u32;
+            }
+        }
+
+        let (base_w, base_h) = self.effective_base_size();
+        let (inc_w, inc_h) = self.effective_resize_increments();
+        // Rounding up to the minimum can overshoot the maximum if the minimum is not on the
+        // increment grid. The result is then clamped to the maximum and lies off the grid.
+        let round = |v: u32, base: u32, inc: u32, min: u32, max: u32| {
+            if v < base {
+                return v;
+            }
+            let mut v = base + (v - base) / inc * inc;
+            if v < min {
+                v = v.saturating_add(inc).min(max);
+            }
+            v
+        };
+        w = round(w, base_w, inc_w, min_w, max_w);
+        h = round(h, base_h, inc_h, min_h, max_h);
+
+        (w.max(min_w).max(1), h.max(min_h).max(1))
+    }
+
+    /// Returns the size in resize increments, e.g. the number of columns and rows of a
+    /// terminal.
+    ///
+    /// Returns `None` if the hints contain no resize increments.
+    pub fn cells(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+        self.resize_increments()?;
+        let (base_w, base_h) = self.effective_base_size();
+        let (inc_w, inc_h) = self.effective_resize_increments();
+        Some((
+            width.saturating_sub(base_w) / inc_w,
+            height.saturating_sub(base_h) / inc_h,
+        ))
+    }
+
+    /// Checks that the hints do not contradict each other.
+    pub fn validate(&self) -> Result<(), XcbSizeHintsConflict> {
+        if let (Some(min), Some(max)) = (self.min_size(), self.max_size()) {
+            let (max_w, max_h) = self.effective_max_size();
+            if min.0 > max_w || min.1 > max_h {
+                return Err(XcbSizeHintsConflict::MinLargerThanMax { min, max });
+            }
+        }
+        if let (Some(base), Some(max)) = (self.base_size(), self.max_size()) {
+            let (max_w, max_h) = self.effective_max_size();
+            if base.0 > max_w || base.1 > max_h {
+                return Err(XcbSizeHintsConflict::BaseLargerThanMax { base, max });
+            }
+        }
+        if let Some((w, h)) = self.resize_increments() {
+            if w == 0 || h == 0 {
+                return Err(XcbSizeHintsConflict::ZeroResizeIncrement);
+            }
+        }
+        if let Some((min, max)) = self.aspect_ratios() {
+            if min.y == 0 || max.y == 0 {
+                return Err(XcbSizeHintsConflict::ZeroAspectDenominator);
+            }
+            if min.x as u64 * max.y as u64 > max.x as u64 * min.y as u64 {
+                return Err(XcbSizeHintsConflict::MinAspectLargerThanMax { min, max });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Error)]
+pub enum XcbSizeHintsConflict {
+    #[error("The minimum size {min:?} is larger than the maximum size {max:?}")]
+    MinLargerThanMax { min: (u32, u32), max: (u32, u32) },
+    #[error("The base size {base:?} is larger than the maximum size {max:?}")]
+    BaseLargerThanMax { base: (u32, u32), max: (u32, u32) },
+    #[error("A resize increment is 0")]
+    ZeroResizeIncrement,
+    #[error("An aspect ratio has a denominator of 0")]
+    ZeroAspectDenominator,
+    #[error("The minimum aspect ratio {min:?} is larger than the maximum aspect ratio {max:?}")]
+    MinAspectLargerThanMax { min: XcbAspect, max: XcbAspect },
+}
+
 #[derive(Clone, Debug, Error)]
 pub enum XcbSizeHintsError {
     #[error("The data is too small to be an XcbSizeHints object")]
     WrongSize,
 }
 
+/// Accepts the 18-word ICCCM layout and the 15-word pre-ICCCM layout.
+///
+/// In the pre-ICCCM layout, the base size and window gravity are unset. Additional words
+/// are ignored.
 impl<'a> TryFrom<&'a [u32]> for XcbSizeHints {
     type Error = XcbSizeHintsError;
 
     fn try_from(value: &'a [u32]) -> Result<Self, Self::Error> {
-        if value.len() != SIZE_HINTS_LEN {
+        if value.len() < OLD_SIZE_HINTS_LEN {
             return Err(XcbSizeHintsError::WrongSize);
         }
-        unsafe { Ok(ptr::read(value.as_ptr() as *const XcbSizeHints)) }
+        let len = value.len().min(SIZE_HINTS_LEN);
+        let mut hints = XcbSizeHints::default();
+        unsafe {
+            ptr::copy_nonoverlapping(value.as_ptr(), &mut hints as *mut _ as *mut u32, len);
+        }
+        if len < SIZE_HINTS_LEN {
+            hints.flags &= !(XcbSizeHintsFlags::P_BASE_SIZE | XcbSizeHintsFlags::P_WINDOW_GRAVITY);
+        }
+        Ok(hints)
     }
 }
 
+#[derive(Clone, Debug, Error)]
+pub enum XcbGetSizeHintsError {
+    #[error("Could not read the property: {0}")]
+    Property(#[from] XcbGetPropertyError),
+    #[error("The property is invalid: {0}")]
+    SizeHints(#[from] XcbSizeHintsError),
+}
+
+pub unsafe fn get_wm_normal_hints(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+) -> Result<XcbSizeHints, XcbGetSizeHintsError> {
+    let value = get_u32_property(
+        xcb,
+        errors,
+        window,
+        XCB_ATOM_WM_NORMAL_HINTS,
+        XCB_ATOM_WM_SIZE_HINTS,
+        SIZE_HINTS_LEN,
+    )?;
+    Ok(XcbSizeHints::try_from(&value[..])?)
+}
+
+pub unsafe fn set_wm_normal_hints(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    hints: &XcbSizeHints,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        XCB_ATOM_WM_NORMAL_HINTS,
+        XCB_ATOM_WM_SIZE_HINTS,
+        hints.as_bytes(),
+    )
+}
+
 bitflags::bitflags! {
     #[derive(Default)]
     pub struct XcbHintsFlags: u32 {
@@ -196,14 +523,19 @@ bitflags::bitflags! {
         const STATE = 1 << 1;
         const ICON_PIXMAP = 1 << 2;
         const ICON_WINDOW = 1 << 3;
-        const P_SIZE = 1 << 4;
-        const ICON_POSITION = 1 << 5;
+        const ICON_POSITION = 1 << 4;
+        const ICON_MASK = 1 << 5;
         const WINDOW_GROUP = 1 << 6;
         const MESSAGE = 1 << 7;
         const URGENCY = 1 << 8;
     }
 }
 
+impl XcbHintsFlags {
+    #[deprecated(note = "this flag is IconPositionHint, use ICON_POSITION")]
+    pub const P_SIZE: Self = Self::ICON_POSITION;
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 #[repr(C)]
 pub struct XcbHints {
@@ -219,6 +551,8 @@ pub struct XcbHints {
 }
 
 const HINTS_LEN: usize = mem::size_of::<XcbHints>() / 4;
+/// The length of the pre-ICCCM layout which lacks the window group.
+const OLD_HINTS_LEN: usize = 8;
 
 // Compile time checks
 const _HINTS_REM: [usize; mem::size_of::<XcbHints>() % 4] = [];
@@ -229,7 +563,7 @@ impl XcbHints {
         unsafe { std::slice::from_raw_parts(self as *const _ as _, HINTS_LEN) }
     }
 
-    pub fn urgency(&mut self) -> bool {
+    pub fn urgency(&self) -> bool {
         self.flags.contains(XcbHintsFlags::URGENCY)
     }
 
@@ -241,14 +575,39 @@ impl XcbHints {
         }
     }
 
+    /// The obsolete message hint. It has no associated data.
+    pub fn message(&self) -> bool {
+        self.flags.contains(XcbHintsFlags::MESSAGE)
+    }
+
+    pub fn set_message(&mut self, message: bool) {
+        if message {
+            self.flags |= XcbHintsFlags::MESSAGE
+        } else {
+            self.flags &= !XcbHintsFlags::MESSAGE
+        }
+    }
+
+    /// Returns `None` if the hint is not set or contains an invalid state.
+    pub fn initial_state(&self) -> Option<XcbWmState> {
+        if self.flags.contains(XcbHintsFlags::STATE) {
+            XcbWmState::try_from(self.initial_state).ok()
+        } else {
+            None
+        }
+    }
+
+    pub fn set_initial_state(&mut self, state: Option<XcbWmState>) {
+        if let Some(state) = state {
+            self.flags |= XcbHintsFlags::STATE;
+            self.initial_state = state.into();
+        } else {
+            self.flags &= !XcbHintsFlags::STATE;
+            self.initial_state = 0;
+        }
+    }
+
     field!(input, set_input, (u32), (input), XcbHintsFlags::INPUT);
-    field!(
-        initial_state,
-        set_initial_state,
-        (u32),
-        (input),
-        XcbHintsFlags::STATE
-    );
     field!(
         icon_pixmap,
         set_icon_pixmap,
@@ -270,6 +629,13 @@ impl XcbHints {
         (icon_x, icon_y),
         XcbHintsFlags::ICON_POSITION
     );
+    field!(
+        icon_mask,
+        set_icon_mask,
+        (xcb_pixmap_t),
+        (icon_mask),
+        XcbHintsFlags::ICON_MASK
+    );
     field!(
         window_group,
         set_window_group,
@@ -285,13 +651,1006 @@ pub enum XcbHintsError {
     WrongSize,
 }
 
+/// Accepts the 9-word ICCCM layout and the 8-word pre-ICCCM layout.
+///
+/// In the pre-ICCCM layout, the window group is unset. Additional words are ignored.
 impl<'a> TryFrom<&'a [u32]> for XcbHints {
     type Error = XcbHintsError;
 
     fn try_from(value: &'a [u32]) -> Result<Self, Self::Error> {
-        if value.len() != HINTS_LEN {
+        if value.len() < OLD_HINTS_LEN {
             return Err(XcbHintsError::WrongSize);
         }
-        unsafe { Ok(ptr::read(value.as_ptr() as *const XcbHints)) }
+        let len = value.len().min(HINTS_LEN);
+        let mut hints = XcbHints::default();
+        unsafe {
+            ptr::copy_nonoverlapping(value.as_ptr(), &mut hints as *mut _ as *mut u32, len);
+        }
+        if len < HINTS_LEN {
+            hints.flags &= !XcbHintsFlags::WINDOW_GROUP;
+        }
+        Ok(hints)
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbGetHintsError {
+    #[error("Could not read the property: {0}")]
+    Property(#[from] XcbGetPropertyError),
+    #[error("The property is invalid: {0}")]
+    Hints(#[from] XcbHintsError),
+}
+
+pub unsafe fn get_wm_hints(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+) -> Result<XcbHints, XcbGetHintsError> {
+    let value = get_u32_property(
+        xcb,
+        errors,
+        window,
+        XCB_ATOM_WM_HINTS,
+        XCB_ATOM_WM_HINTS,
+        HINTS_LEN,
+    )?;
+    Ok(XcbHints::try_from(&value[..])?)
+}
+
+pub unsafe fn set_wm_hints(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    hints: &XcbHints,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        XCB_ATOM_WM_HINTS,
+        XCB_ATOM_WM_HINTS,
+        hints.as_bytes(),
+    )
+}
+
+bitflags::bitflags! {
+    #[derive(Default)]
+    pub struct XcbMotifHintsFlags: u32 {
+        const FUNCTIONS = 1 << 0;
+        const DECORATIONS = 1 << 1;
+        const INPUT_MODE = 1 << 2;
+        const STATUS = 1 << 3;
+    }
+}
+
+bitflags::bitflags! {
+    /// The functions that the window manager should offer.
+    ///
+    /// If `ALL` is set, the other flags denote the functions that should not be offered.
+    #[derive(Default)]
+    pub struct XcbMotifFunctions: u32 {
+        const ALL = 1 << 0;
+        const RESIZE = 1 << 1;
+        const MOVE = 1 << 2;
+        const MINIMIZE = 1 << 3;
+        const MAXIMIZE = 1 << 4;
+        const CLOSE = 1 << 5;
+    }
+}
+
+bitflags::bitflags! {
+    /// The decorations that the window manager should draw.
+    ///
+    /// If `ALL` is set, the other flags denote the decorations that should not be drawn.
+    #[derive(Default)]
+    pub struct XcbMotifDecorations: u32 {
+        const ALL = 1 << 0;
+        const BORDER = 1 << 1;
+        const RESIZE_HANDLE = 1 << 2;
+        const TITLE = 1 << 3;
+        const MENU = 1 << 4;
+        const MINIMIZE = 1 << 5;
+        const MAXIMIZE = 1 << 6;
+    }
+}
+
+bitflags::bitflags! {
+    #[derive(Default)]
+    pub struct XcbMotifStatus: u32 {
+        const TEAROFF_WINDOW = 1 << 0;
+    }
+}
+
+impl XcbMotifFunctions {
+    /// Resolves `ALL` into the set of functions that should be offered.
+    pub fn resolve(self) -> Self {
+        if self.contains(Self::ALL) {
+            Self::all() - Self::ALL - self
+        } else {
+            self
+        }
+    }
+}
+
+impl XcbMotifDecorations {
+    /// Resolves `ALL` into the set of decorations that should be drawn.
+    pub fn resolve(self) -> Self {
+        if self.contains(Self::ALL) {
+            Self::all() - Self::ALL - self
+        } else {
+            self
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbMotifInputMode {
+    Modeless,
+    PrimaryApplicationModal,
+    SystemModal,
+    FullApplicationModal,
+}
+
+impl TryFrom<i32> for XcbMotifInputMode {
+    type Error = i32;
+
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(XcbMotifInputMode::Modeless),
+            1 => Ok(XcbMotifInputMode::PrimaryApplicationModal),
+            2 => Ok(XcbMotifInputMode::SystemModal),
+            3 => Ok(XcbMotifInputMode::FullApplicationModal),
+            _ => Err(v),
+        }
+    }
+}
+
+impl From<XcbMotifInputMode> for i32 {
+    fn from(m: XcbMotifInputMode) -> Self {
+        match m {
+            XcbMotifInputMode::Modeless => 0,
+            XcbMotifInputMode::PrimaryApplicationModal => 1,
+            XcbMotifInputMode::SystemModal => 2,
+            XcbMotifInputMode::FullApplicationModal => 3,
+        }
+    }
+}
+
+/// The contents of the _MOTIF_WM_HINTS property.
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct XcbMotifHints {
+    pub flags: XcbMotifHintsFlags,
+    pub functions: XcbMotifFunctions,
+    pub decorations: XcbMotifDecorations,
+    pub input_mode: i32,
+    pub status: XcbMotifStatus,
+}
+
+const MOTIF_HINTS_LEN: usize = mem::size_of::<XcbMotifHints>() / 4;
+
+// Compile time checks
+const _MOTIF_HINTS_REM: [usize; mem::size_of::<XcbMotifHints>() % 4] = [];
+const _MOTIF_HINTS_ALIGN: [usize; mem::align_of::<XcbMotifHints>() - mem::align_of::<u32>()] = [];
+
+impl XcbMotifHints {
+    pub fn as_bytes(&self) -> &[u32] {
+        unsafe { std::slice::from_raw_parts(self as *const _ as _, MOTIF_HINTS_LEN) }
+    }
+
+    /// Returns `None` if the hint is not set or contains an invalid mode.
+    pub fn input_mode(&self) -> Option<XcbMotifInputMode> {
+        if self.flags.contains(XcbMotifHintsFlags::INPUT_MODE) {
+            XcbMotifInputMode::try_from(self.input_mode).ok()
+        } else {
+            None
+        }
+    }
+
+    pub fn set_input_mode(&mut self, mode: Option<XcbMotifInputMode>) {
+        if let Some(mode) = mode {
+            self.flags |= XcbMotifHintsFlags::INPUT_MODE;
+            self.input_mode = mode.into();
+        } else {
+            self.flags &= !XcbMotifHintsFlags::INPUT_MODE;
+            self.input_mode = 0;
+        }
+    }
+
+    field!(
+        functions,
+        set_functions,
+        (XcbMotifFunctions),
+        (functions),
+        XcbMotifHintsFlags::FUNCTIONS
+    );
+    field!(
+        decorations,
+        set_decorations,
+        (XcbMotifDecorations),
+        (decorations),
+        XcbMotifHintsFlags::DECORATIONS
+    );
+    field!(
+        status,
+        set_status,
+        (XcbMotifStatus),
+        (status),
+        XcbMotifHintsFlags::STATUS
+    );
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbMotifHintsError {
+    #[error("The data is empty")]
+    Empty,
+}
+
+impl<'a> TryFrom<&'a [u32]> for XcbMotifHints {
+    type Error = XcbMotifHintsError;
+
+    /// Shorter data is accepted and the hints whose fields are missing are unset. Longer
+    /// data is truncated. Unknown bits are ignored.
+    fn try_from(value: &'a [u32]) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Err(XcbMotifHintsError::Empty);
+        }
+        let mut hints = XcbMotifHints {
+            flags: XcbMotifHintsFlags::from_bits_truncate(value[0]),
+            ..Default::default()
+        };
+        let field = |i: usize, flag: XcbMotifHintsFlags| match value.get(i) {
+            Some(&v) if hints.flags.contains(flag) => Some(v),
+            _ => None,
+        };
+        let functions = field(1, XcbMotifHintsFlags::FUNCTIONS);
+        let decorations = field(2, XcbMotifHintsFlags::DECORATIONS);
+        let input_mode = field(3, XcbMotifHintsFlags::INPUT_MODE);
+        let status = field(4, XcbMotifHintsFlags::STATUS);
+        hints.set_functions(functions.map(XcbMotifFunctions::from_bits_truncate));
+        hints.set_decorations(decorations.map(XcbMotifDecorations::from_bits_truncate));
+        hints.flags &= !XcbMotifHintsFlags::INPUT_MODE;
+        if let Some(mode) = input_mode {
+            hints.flags |= XcbMotifHintsFlags::INPUT_MODE;
+            hints.input_mode = mode as i32;
+        }
+        hints.set_status(status.map(XcbMotifStatus::from_bits_truncate));
+        Ok(hints)
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbGetMotifHintsError {
+    #[error("Could not read the property: {0}")]
+    Property(#[from] XcbGetPropertyError),
+    #[error("The property is invalid: {0}")]
+    MotifHints(#[from] XcbMotifHintsError),
+}
+
+/// Reads the _MOTIF_WM_HINTS property.
+///
+/// `motif_wm_hints` is the _MOTIF_WM_HINTS atom which is also the type of the property.
+pub unsafe fn get_motif_wm_hints(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    motif_wm_hints: xcb_atom_t,
+    window: xcb_window_t,
+) -> Result<XcbMotifHints, XcbGetMotifHintsError> {
+    let value = get_u32_property(
+        xcb,
+        errors,
+        window,
+        motif_wm_hints,
+        motif_wm_hints,
+        MOTIF_HINTS_LEN,
+    )?;
+    Ok(XcbMotifHints::try_from(&value[..])?)
+}
+
+pub unsafe fn set_motif_wm_hints(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    motif_wm_hints: xcb_atom_t,
+    window: xcb_window_t,
+    hints: &XcbMotifHints,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        motif_wm_hints,
+        motif_wm_hints,
+        hints.as_bytes(),
+    )
+}
+
+/// The ICCCM state of a top-level window.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbWmState {
+    Withdrawn,
+    Normal,
+    Iconic,
+}
+
+impl TryFrom<u32> for XcbWmState {
+    type Error = XcbWmStateError;
+
+    fn try_from(v: u32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(XcbWmState::Withdrawn),
+            1 => Ok(XcbWmState::Normal),
+            3 => Ok(XcbWmState::Iconic),
+            _ => Err(XcbWmStateError::InvalidState(v)),
+        }
+    }
+}
+
+impl From<XcbWmState> for u32 {
+    fn from(s: XcbWmState) -> Self {
+        match s {
+            XcbWmState::Withdrawn => 0,
+            XcbWmState::Normal => 1,
+            XcbWmState::Iconic => 3,
+        }
+    }
+}
+
+/// The contents of the WM_STATE property.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct XcbWmStateProperty {
+    pub state: XcbWmState,
+    pub icon: xcb_window_t,
+}
+
+const WM_STATE_LEN: usize = 2;
+
+impl XcbWmStateProperty {
+    pub fn as_bytes(&self) -> [u32; WM_STATE_LEN] {
+        [self.state.into(), self.icon]
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbWmStateError {
+    #[error("The data is too small to be an XcbWmStateProperty object")]
+    WrongSize,
+    #[error("Invalid window state {0}")]
+    InvalidState(u32),
+}
+
+impl<'a> TryFrom<&'a [u32]> for XcbWmStateProperty {
+    type Error = XcbWmStateError;
+
+    fn try_from(value: &'a [u32]) -> Result<Self, Self::Error> {
+        if value.len() != WM_STATE_LEN {
+            return Err(XcbWmStateError::WrongSize);
+        }
+        Ok(Self {
+            state: XcbWmState::try_from(value[0])?,
+            icon: value[1],
+        })
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbGetWmStateError {
+    #[error("Could not read the property: {0}")]
+    Property(#[from] XcbGetPropertyError),
+    #[error("The property is invalid: {0}")]
+    WmState(#[from] XcbWmStateError),
+}
+
+/// Reads the WM_STATE property. `wm_state` is the WM_STATE atom.
+pub unsafe fn get_wm_state(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    wm_state: xcb_atom_t,
+    window: xcb_window_t,
+) -> Result<XcbWmStateProperty, XcbGetWmStateError> {
+    let value = get_u32_property(xcb, errors, window, wm_state, wm_state, WM_STATE_LEN)?;
+    Ok(XcbWmStateProperty::try_from(&value[..])?)
+}
+
+/// Writes the WM_STATE property. `wm_state` is the WM_STATE atom.
+pub unsafe fn set_wm_state(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    wm_state: xcb_atom_t,
+    window: xcb_window_t,
+    state: &XcbWmStateProperty,
+) -> XcbPendingCommands {
+    set_property(xcb, c, window, wm_state, wm_state, &state.as_bytes())
+}
+
+const TEXT_STEP: u32 = 1024;
+/// Text properties are read from untrusted clients. Longer values are truncated.
+const TEXT_LIMIT: XcbPropertyLimit = XcbPropertyLimit {
+    max_bytes: 64 * 1024,
+    policy: XcbPropertyLimitPolicy::Truncate,
+};
+
+crate::atoms! {
+    /// The atoms used by the identity properties that are not predefined. Text encodings use
+    /// `XcbTextAtoms`.
+    pub struct XcbIcccmAtoms {
+        UTF8_STRING,
+        _NET_WM_NAME,
+        _NET_WM_ICON_NAME,
+        WM_CLIENT_LEADER,
+    }
+}
+
+/// Reads a text property. Values longer than 64 KiB are truncated.
+pub unsafe fn get_text_property(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+) -> Result<XcbTextProperty, XcbGetPropertyError> {
+    let value = request_any_property(xcb, errors.c, window, property, false, TEXT_STEP)
+        .reply_limited(xcb, errors, TEXT_LIMIT)?;
+    text_property(value.value)
+}
+
+pub unsafe fn set_text_property(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    text: &XcbTextProperty,
+) -> XcbPendingCommands {
+    set_property(xcb, c, window, property, text.encoding, &text.value[..])
+}
+
+fn text_property(value: XcbAnyProperty) -> Result<XcbTextProperty, XcbGetPropertyError> {
+    match value.value {
+        XcbPropertyValue::U8(v) => Ok(XcbTextProperty {
+            encoding: value.type_,
+            value: v.into(),
+        }),
+        v => Err(XcbGetPropertyError::InvalidPropertyFormat {
+            expected: 8,
+            actual: v.format(),
+        }),
+    }
+}
+
+/// Reads WM_NAME. Like all text properties, it is truncated after 64 KiB.
+pub unsafe fn get_wm_name(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+) -> Result<XcbTextProperty, XcbGetPropertyError> {
+    get_text_property(xcb, errors, window, XCB_ATOM_WM_NAME)
+}
+
+pub unsafe fn set_wm_name(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    name: &XcbTextProperty,
+) -> XcbPendingCommands {
+    set_text_property(xcb, c, window, XCB_ATOM_WM_NAME, name)
+}
+
+/// Reads WM_ICON_NAME. See `get_wm_name` for the size limit.
+pub unsafe fn get_wm_icon_name(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+) -> Result<XcbTextProperty, XcbGetPropertyError> {
+    get_text_property(xcb, errors, window, XCB_ATOM_WM_ICON_NAME)
+}
+
+pub unsafe fn set_wm_icon_name(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    name: &XcbTextProperty,
+) -> XcbPendingCommands {
+    set_text_property(xcb, c, window, XCB_ATOM_WM_ICON_NAME, name)
+}
+
+/// Reads WM_CLIENT_MACHINE. See `get_wm_name` for the size limit.
+pub unsafe fn get_wm_client_machine(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+) -> Result<XcbTextProperty, XcbGetPropertyError> {
+    get_text_property(xcb, errors, window, XCB_ATOM_WM_CLIENT_MACHINE)
+}
+
+pub unsafe fn set_wm_client_machine(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    name: &XcbTextProperty,
+) -> XcbPendingCommands {
+    set_text_property(xcb, c, window, XCB_ATOM_WM_CLIENT_MACHINE, name)
+}
+
+/// Reads the title of a window.
+///
+/// _NET_WM_NAME is preferred. If it is not set or not of type UTF8_STRING, WM_NAME is
+/// used. Both properties are requested with a single round trip. Titles longer than
+/// 64 KiB are truncated.
+pub unsafe fn get_title(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbIcccmAtoms,
+    window: xcb_window_t,
+) -> Result<XcbTextProperty, XcbGetPropertyError> {
+    get_preferred_text(
+        xcb,
+        errors,
+        atoms,
+        window,
+        atoms._NET_WM_NAME,
+        XCB_ATOM_WM_NAME,
+    )
+}
+
+/// Reads the icon title of a window.
+///
+/// _NET_WM_ICON_NAME is preferred. If it is not set or not of type UTF8_STRING,
+/// WM_ICON_NAME is used. As with `get_title`, the value is truncated after 64 KiB.
+pub unsafe fn get_icon_title(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbIcccmAtoms,
+    window: xcb_window_t,
+) -> Result<XcbTextProperty, XcbGetPropertyError> {
+    get_preferred_text(
+        xcb,
+        errors,
+        atoms,
+        window,
+        atoms._NET_WM_ICON_NAME,
+        XCB_ATOM_WM_ICON_NAME,
+    )
+}
+
+unsafe fn get_preferred_text(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbIcccmAtoms,
+    window: xcb_window_t,
+    net_property: xcb_atom_t,
+    property: xcb_atom_t,
+) -> Result<XcbTextProperty, XcbGetPropertyError> {
+    let net = request_property::<u8>(
+        xcb,
+        errors.c,
+        window,
+        net_property,
+        atoms.UTF8_STRING,
+        false,
+        TEXT_STEP,
+    );
+    let fallback = request_any_property(xcb, errors.c, window, property, false, TEXT_STEP);
+    match net.reply_limited(xcb, errors, TEXT_LIMIT) {
+        Ok(value) => {
+            fallback.discard(xcb, errors.c);
+            Ok(XcbTextProperty {
+                encoding: atoms.UTF8_STRING,
+                value: value.value.into(),
+            })
+        }
+        Err(XcbGetPropertyError::Xcb(e)) => {
+            fallback.discard(xcb, errors.c);
+            Err(e.into())
+        }
+        Err(_) => text_property(fallback.reply_limited(xcb, errors, TEXT_LIMIT)?.value),
+    }
+}
+
+/// The contents of the WM_CLASS property.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct XcbWmClass {
+    pub instance: BString,
+    pub class: BString,
+}
+
+/// Reads WM_CLASS. Only the first 64 KiB of the property are read.
+pub unsafe fn get_wm_class(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+) -> Result<XcbWmClass, XcbGetPropertyError> {
+    let value = get_property_limited::<u8>(
+        xcb,
+        errors,
+        window,
+        XCB_ATOM_WM_CLASS,
+        XCB_ATOM_STRING,
+        false,
+        TEXT_STEP,
+        TEXT_LIMIT,
+    )?
+    .value;
+    let mut strings = split_strings(&value).into_iter().map(BString::from);
+    Ok(XcbWmClass {
+        instance: strings.next().unwrap_or_default(),
+        class: strings.next().unwrap_or_default(),
+    })
+}
+
+pub unsafe fn set_wm_class(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    class: &XcbWmClass,
+) -> XcbPendingCommands {
+    let value = join_strings(&[&class.instance[..], &class.class[..]]);
+    set_property(xcb, c, window, XCB_ATOM_WM_CLASS, XCB_ATOM_STRING, &value)
+}
+
+/// Reads the command line that was used to start the client.
+///
+/// The property is truncated after 64 KiB. The last argument can therefore be incomplete.
+pub unsafe fn get_wm_command(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+) -> Result<Vec<BString>, XcbGetPropertyError> {
+    let value = get_property_limited::<u8>(
+        xcb,
+        errors,
+        window,
+        XCB_ATOM_WM_COMMAND,
+        XCB_ATOM_STRING,
+        false,
+        TEXT_STEP,
+        TEXT_LIMIT,
+    )?
+    .value;
+    Ok(split_strings(&value)
+        .into_iter()
+        .map(BString::from)
+        .collect())
+}
+
+pub unsafe fn set_wm_command<A: AsRef<[u8]>>(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    argv: &[A],
+) -> XcbPendingCommands {
+    let value = join_strings(argv);
+    set_property(xcb, c, window, XCB_ATOM_WM_COMMAND, XCB_ATOM_STRING, &value)
+}
+
+pub unsafe fn get_wm_transient_for(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+) -> Result<xcb_window_t, XcbGetPropertyError> {
+    get_u32_value(
+        xcb,
+        errors,
+        window,
+        XCB_ATOM_WM_TRANSIENT_FOR,
+        XCB_ATOM_WINDOW,
+    )
+}
+
+pub unsafe fn set_wm_transient_for(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    transient_for: xcb_window_t,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        XCB_ATOM_WM_TRANSIENT_FOR,
+        XCB_ATOM_WINDOW,
+        &[transient_for],
+    )
+}
+
+pub unsafe fn get_wm_client_leader(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbIcccmAtoms,
+    window: xcb_window_t,
+) -> Result<xcb_window_t, XcbGetPropertyError> {
+    get_u32_value(xcb, errors, window, atoms.WM_CLIENT_LEADER, XCB_ATOM_WINDOW)
+}
+
+pub unsafe fn set_wm_client_leader(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbIcccmAtoms,
+    window: xcb_window_t,
+    leader: xcb_window_t,
+) -> XcbPendingCommands {
+    set_property(
+        xcb,
+        c,
+        window,
+        atoms.WM_CLIENT_LEADER,
+        XCB_ATOM_WINDOW,
+        &[leader],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aspect(x: u32, y: u32) -> XcbAspect {
+        XcbAspect { x, y }
+    }
+
+    #[test]
+    fn xterm_increments() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_base_size(Some((10, 4)));
+        hints.set_resize_increments(Some((6, 13)));
+        hints.set_min_size(Some((16, 17)));
+        assert_eq!(hints.constrain(500, 300), (496, 290));
+        assert_eq!(hints.cells(496, 290), Some((81, 22)));
+        assert_eq!(hints.constrain(5, 5), (16, 17));
+        assert!(hints.validate().is_ok());
+    }
+
+    #[test]
+    fn base_falls_back_to_min() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_min_size(Some((10, 10)));
+        hints.set_resize_increments(Some((5, 5)));
+        assert_eq!(hints.constrain(23, 27), (20, 25));
+        assert_eq!(hints.cells(20, 25), Some((2, 3)));
+    }
+
+    #[test]
+    fn aspect_too_wide() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_aspect_ratios(Some((aspect(1, 1), aspect(1, 1))));
+        assert_eq!(hints.constrain(200, 100), (100, 100));
+    }
+
+    #[test]
+    fn aspect_too_tall() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_aspect_ratios(Some((aspect(1, 1), aspect(1, 1))));
+        assert_eq!(hints.constrain(100, 200), (100, 100));
+    }
+
+    #[test]
+    fn aspect_range() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_aspect_ratios(Some((aspect(4, 3), aspect(16, 9))));
+        assert_eq!(hints.constrain(1600, 1000), (1600, 1000));
+        assert_eq!(hints.constrain(1000, 1000), (1000, 750));
+        assert_eq!(hints.constrain(1920, 900), (1600, 900));
+    }
+
+    #[test]
+    fn min_takes_precedence_over_max() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_min_size(Some((200, 200)));
+        hints.set_max_size(Some((100, 100)));
+        assert_eq!(hints.constrain(150, 150), (200, 200));
+        assert!(matches!(
+            hints.validate(),
+            Err(XcbSizeHintsConflict::MinLargerThanMax { .. })
+        ));
+    }
+
+    #[test]
+    fn zero_max_is_unlimited() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_min_size(Some((10, 10)));
+        hints.set_max_size(Some((0, 100)));
+        assert_eq!(hints.constrain(5000, 5000), (5000, 100));
+        assert!(hints.validate().is_ok());
+    }
+
+    #[test]
+    fn increment_does_not_exceed_max() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_base_size(Some((0, 0)));
+        hints.set_min_size(Some((15, 15)));
+        hints.set_max_size(Some((18, 100)));
+        hints.set_resize_increments(Some((10, 10)));
+        assert_eq!(hints.constrain(18, 18), (18, 20));
+    }
+
+    #[test]
+    fn validate_conflicts() {
+        let mut hints = XcbSizeHints::default();
+        hints.set_resize_increments(Some((0, 1)));
+        assert!(matches!(
+            hints.validate(),
+            Err(XcbSizeHintsConflict::ZeroResizeIncrement)
+        ));
+        let mut hints = XcbSizeHints::default();
+        hints.set_aspect_ratios(Some((aspect(2, 1), aspect(1, 1))));
+        assert!(matches!(
+            hints.validate(),
+            Err(XcbSizeHintsConflict::MinAspectLargerThanMax { .. })
+        ));
+        let mut hints = XcbSizeHints::default();
+        hints.set_base_size(Some((50, 50)));
+        hints.set_max_size(Some((40, 0)));
+        assert!(matches!(
+            hints.validate(),
+            Err(XcbSizeHintsConflict::BaseLargerThanMax { .. })
+        ));
+    }
+
+    #[test]
+    fn hints_layouts() {
+        let flags = (XcbHintsFlags::INPUT | XcbHintsFlags::WINDOW_GROUP).bits();
+        let hints = XcbHints::try_from(&[flags, 1, 0, 0, 0, 0, 0, 0][..]).unwrap();
+        assert_eq!(hints.flags, XcbHintsFlags::INPUT);
+        assert_eq!(hints.window_group(), None);
+        let hints = XcbHints::try_from(&[flags, 1, 0, 0, 0, 0, 0, 0, 7, 8][..]).unwrap();
+        assert_eq!(hints.window_group(), Some(7));
+        assert!(XcbHints::try_from(&[flags; 7][..]).is_err());
+    }
+
+    const GRAVITIES: [XcbGravity; 11] = [
+        XcbGravity::WIN_UNMAP,
+        XcbGravity::NORTH_WEST,
+        XcbGravity::NORTH,
+        XcbGravity::NORTH_EAST,
+        XcbGravity::WEST,
+        XcbGravity::CENTER,
+        XcbGravity::EAST,
+        XcbGravity::SOUTH_WEST,
+        XcbGravity::SOUTH,
+        XcbGravity::SOUTH_EAST,
+        XcbGravity::STATIC,
+    ];
+
+    const CLIENT: XcbGeometry = XcbGeometry {
+        x: 100,
+        y: 100,
+        width: 200,
+        height: 150,
+    };
+
+    const EXTENTS: XcbFrameExtents = XcbFrameExtents {
+        left: 4,
+        right: 6,
+        top: 22,
+        bottom: 6,
+    };
+
+    #[test]
+    fn reference_points() {
+        let points = [
+            (XcbGravity::WIN_UNMAP, (0, 0)),
+            (XcbGravity::NORTH_WEST, (0, 0)),
+            (XcbGravity::NORTH, (50, 0)),
+            (XcbGravity::NORTH_EAST, (100, 0)),
+            (XcbGravity::WEST, (0, 25)),
+            (XcbGravity::CENTER, (50, 25)),
+            (XcbGravity::EAST, (100, 25)),
+            (XcbGravity::SOUTH_WEST, (0, 50)),
+            (XcbGravity::SOUTH, (50, 50)),
+            (XcbGravity::SOUTH_EAST, (100, 50)),
+            (XcbGravity::STATIC, (0, 0)),
+        ];
+        for (gravity, point) in points {
+            assert_eq!(gravity.reference_offset(100, 50), point, "{:?}", gravity);
+        }
+    }
+
+    #[test]
+    fn frame_keeps_reference_point() {
+        let bw = 2;
+        for gravity in GRAVITIES {
+            let frame = gravity.client_to_frame(CLIENT, bw, &EXTENTS);
+            assert_eq!(frame.width, 210, "{:?}", gravity);
+            assert_eq!(frame.height, 178, "{:?}", gravity);
+            if gravity == XcbGravity::STATIC {
+                // The client area inside the border stays in place.
+                assert_eq!(frame.x + EXTENTS.left as i32, CLIENT.x + bw as i32);
+                assert_eq!(frame.y + EXTENTS.top as i32, CLIENT.y + bw as i32);
+                continue;
+            }
+            // The reference point of the client includes its border.
+            let (cx, cy) = gravity.reference_offset(CLIENT.width + 2 * bw, CLIENT.height + 2 * bw);
+            let (fx, fy) = gravity.reference_offset(frame.width, frame.height);
+            assert_eq!(
+                (frame.x + fx, frame.y + fy),
+                (CLIENT.x + cx, CLIENT.y + cy),
+                "{:?}",
+                gravity
+            );
+        }
+    }
+
+    #[test]
+    fn frame_round_trip() {
+        for bw in [0, 1, 5] {
+            for gravity in GRAVITIES {
+                let frame = gravity.client_to_frame(CLIENT, bw, &EXTENTS);
+                assert_eq!(
+                    gravity.frame_to_client(frame, bw, &EXTENTS),
+                    CLIENT,
+                    "{:?} {}",
+                    gravity,
+                    bw
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn motif_hints_empty() {
+        assert!(matches!(
+            XcbMotifHints::try_from(&[][..]),
+            Err(XcbMotifHintsError::Empty)
+        ));
+    }
+
+    #[test]
+    fn motif_hints_layouts() {
+        let flags = XcbMotifHintsFlags::all().bits();
+        let functions = (XcbMotifFunctions::MOVE | XcbMotifFunctions::CLOSE).bits();
+        let decorations = XcbMotifDecorations::BORDER.bits();
+
+        let hints = XcbMotifHints::try_from(&[flags, functions, decorations][..]).unwrap();
+        let all = XcbMotifHintsFlags::FUNCTIONS | XcbMotifHintsFlags::DECORATIONS;
+        assert_eq!(hints.flags, all);
+        assert_eq!(
+            hints.functions(),
+            Some(XcbMotifFunctions::MOVE | XcbMotifFunctions::CLOSE)
+        );
+        assert_eq!(hints.decorations(), Some(XcbMotifDecorations::BORDER));
+        assert_eq!(hints.input_mode(), None);
+        assert_eq!(hints.status(), None);
+
+        let hints = XcbMotifHints::try_from(&[flags, functions, decorations, 2][..]).unwrap();
+        assert_eq!(hints.flags, all | XcbMotifHintsFlags::INPUT_MODE);
+        assert_eq!(hints.input_mode(), Some(XcbMotifInputMode::SystemModal));
+        assert_eq!(hints.status(), None);
+
+        let hints =
+            XcbMotifHints::try_from(&[flags, functions, decorations, 2, 1, 9, 9][..]).unwrap();
+        assert_eq!(hints.flags, XcbMotifHintsFlags::all());
+        assert_eq!(hints.input_mode(), Some(XcbMotifInputMode::SystemModal));
+        assert_eq!(hints.status(), Some(XcbMotifStatus::TEAROFF_WINDOW));
+    }
+
+    #[test]
+    fn motif_hints_unset_fields_are_ignored() {
+        let flags = XcbMotifHintsFlags::DECORATIONS.bits() | 1 << 31;
+        let hints = XcbMotifHints::try_from(&[flags, !0, 0, 3, 1][..]).unwrap();
+        assert_eq!(hints.flags, XcbMotifHintsFlags::DECORATIONS);
+        assert_eq!(hints.functions(), None);
+        assert_eq!(hints.decorations(), Some(XcbMotifDecorations::empty()));
+        assert_eq!(hints.input_mode(), None);
+        assert_eq!(hints.status(), None);
+    }
+
+    #[test]
+    fn motif_decorations_resolve() {
+        let decorations = XcbMotifDecorations::TITLE | XcbMotifDecorations::MENU;
+        assert_eq!(decorations.resolve(), decorations);
+        assert_eq!(
+            (XcbMotifDecorations::ALL | decorations).resolve(),
+            XcbMotifDecorations::BORDER
+                | XcbMotifDecorations::RESIZE_HANDLE
+                | XcbMotifDecorations::MINIMIZE
+                | XcbMotifDecorations::MAXIMIZE
+        );
+        assert_eq!(
+            XcbMotifDecorations::ALL.resolve(),
+            XcbMotifDecorations::all() - XcbMotifDecorations::ALL
+        );
+        assert_eq!(
+            (XcbMotifFunctions::ALL | XcbMotifFunctions::CLOSE).resolve(),
+            XcbMotifFunctions::RESIZE
+                | XcbMotifFunctions::MOVE
+                | XcbMotifFunctions::MINIMIZE
+                | XcbMotifFunctions::MAXIMIZE
+        );
     }
 }
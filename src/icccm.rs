@@ -0,0 +1,80 @@
+use crate::event::{send_event, send_root_message};
+use crate::hint::{set_wm_hints, XcbHints, XcbWmState};
+use crate::void::{XcbPendingCommand, XcbPendingCommands};
+use xcb_dl::ffi::*;
+use xcb_dl::Xcb;
+
+/// Asks the window manager to move a window from the normal to the iconic state.
+///
+/// `wm_change_state` is the WM_CHANGE_STATE atom.
+pub unsafe fn iconify_window(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    root: xcb_window_t,
+    wm_change_state: xcb_atom_t,
+    window: xcb_window_t,
+) -> XcbPendingCommands {
+    let data = [XcbWmState::Iconic.into(), 0, 0, 0, 0];
+    send_root_message(xcb, c, root, window, wm_change_state, data).into()
+}
+
+/// Moves a window from the iconic to the normal state.
+pub unsafe fn deiconify_window(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+) -> XcbPendingCommands {
+    XcbPendingCommand::new(xcb.xcb_map_window_checked(c, window)).into()
+}
+
+/// Moves a window from the normal or iconic state to the withdrawn state.
+///
+/// Besides unmapping the window, this sends the synthetic UnmapNotify event that informs
+/// the window manager of windows that are already unmapped because they are iconic.
+pub unsafe fn withdraw_window(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    root: xcb_window_t,
+    window: xcb_window_t,
+) -> XcbPendingCommands {
+    let mut commands = XcbPendingCommands::new();
+    commands.push(xcb.xcb_unmap_window_checked(c, window).into());
+    let event = xcb_unmap_notify_event_t {
+        response_type: XCB_UNMAP_NOTIFY,
+        event: root,
+        window,
+        from_configure: 0,
+        ..Default::default()
+    };
+    commands.push(send_event(
+        xcb,
+        c,
+        false,
+        root,
+        XCB_EVENT_MASK_SUBSTRUCTURE_NOTIFY | XCB_EVENT_MASK_SUBSTRUCTURE_REDIRECT,
+        &event,
+    ));
+    commands
+}
+
+/// Moves a window from the withdrawn state to the normal or iconic state.
+///
+/// The initial state is stored in `hints` which are written to the WM_HINTS property
+/// before the window is mapped.
+pub unsafe fn map_withdrawn_window(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    hints: &mut XcbHints,
+    iconic: bool,
+) -> XcbPendingCommands {
+    let state = if iconic {
+        XcbWmState::Iconic
+    } else {
+        XcbWmState::Normal
+    };
+    hints.set_initial_state(Some(state));
+    let mut commands = set_wm_hints(xcb, c, window, hints);
+    commands.push(xcb.xcb_map_window_checked(c, window).into());
+    commands
+}
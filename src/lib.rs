@@ -1,15 +1,28 @@
 //! This crate contain utilities for working with xcb-dl.
 
+pub mod atom;
 #[cfg(feature = "xcb_render")]
 pub mod cursor;
 pub mod error;
+pub mod event;
+pub mod ewmh;
 pub mod format;
 pub mod hint;
+pub mod icccm;
 #[cfg(feature = "xcb_xinput")]
 pub mod input;
 pub mod log;
 pub mod property;
+pub mod protocol;
 #[cfg(feature = "xcb_render")]
 pub mod render;
+#[cfg(feature = "xcb_sync")]
+pub mod sync;
+pub mod text;
 pub mod void;
 pub mod xcb_box;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use xcb_dl;
+}
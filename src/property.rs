@@ -1,6 +1,9 @@
 use crate::error::{XcbError, XcbErrorParser};
 use crate::format::XcbDataType;
-use std::{ptr, slice};
+use crate::void::{XcbPendingCommand, XcbPendingCommands};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::{mem, ptr, slice};
 use thiserror::Error;
 use xcb_dl::ffi::*;
 use xcb_dl::*;
@@ -16,10 +19,150 @@ pub enum XcbGetPropertyError {
     InvalidPropertyFormat { expected: u8, actual: u8 },
     #[error("The property is not set")]
     Unset,
+    #[error("The property is too large (limit: {limit} bytes, actual: {size} bytes)")]
+    TooLarge { limit: u32, size: u64 },
     #[error("xcb error: {0}")]
     Xcb(#[from] XcbError),
 }
 
+/// What to do when a property is larger than the limit.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum XcbPropertyLimitPolicy {
+    /// Fail with `XcbGetPropertyError::TooLarge`.
+    Error,
+    /// Return the data up to the limit and report that it was truncated.
+    Truncate,
+}
+
+/// An upper bound on the number of bytes read from a property.
+///
+/// Properties are controlled by other clients. Without a limit, a client can make the
+/// reader allocate arbitrary amounts of memory.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct XcbPropertyLimit {
+    pub max_bytes: u32,
+    pub policy: XcbPropertyLimitPolicy,
+}
+
+/// The result of a property read with an `XcbPropertyLimit`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XcbLimitedProperty<T> {
+    pub value: T,
+    /// Whether `value` was cut off at the limit.
+    pub truncated: bool,
+}
+
+/// A property read whose first request has been sent but whose reply has not yet been
+/// retrieved.
+///
+/// Issuing many of these before resolving any of them allows the X server to process all
+/// of the requests in a single round trip.
+#[must_use = "XcbPendingProperty panics when dropped."]
+pub struct XcbPendingProperty<T> {
+    request: PropertyRequest,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: XcbDataType> XcbPendingProperty<T> {
+    pub unsafe fn reply(
+        self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+    ) -> Result<Vec<T>, XcbGetPropertyError> {
+        let mut buf = vec![];
+        self.reply_in(xcb, errors, &mut buf)?;
+        Ok(buf)
+    }
+
+    pub unsafe fn reply_in(
+        self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        buf: &mut Vec<T>,
+    ) -> Result<(), XcbGetPropertyError> {
+        self.read_in(xcb, errors, None, buf)?;
+        Ok(())
+    }
+
+    pub unsafe fn reply_limited(
+        self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        limit: XcbPropertyLimit,
+    ) -> Result<XcbLimitedProperty<Vec<T>>, XcbGetPropertyError> {
+        let mut value = vec![];
+        let truncated = self.reply_limited_in(xcb, errors, limit, &mut value)?;
+        Ok(XcbLimitedProperty { value, truncated })
+    }
+
+    /// Returns whether the property was truncated.
+    pub unsafe fn reply_limited_in(
+        self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        limit: XcbPropertyLimit,
+        buf: &mut Vec<T>,
+    ) -> Result<bool, XcbGetPropertyError> {
+        self.read_in(xcb, errors, Some(limit), buf)
+    }
+
+    unsafe fn read_in(
+        self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        limit: Option<XcbPropertyLimit>,
+        buf: &mut Vec<T>,
+    ) -> Result<bool, XcbGetPropertyError> {
+        let slf = ManuallyDrop::new(self);
+        slf.request.read(xcb, errors, limit, |res, len| {
+            if res.type_ != slf.request.type_ {
+                return Err(XcbGetPropertyError::InvalidPropertyType {
+                    expected: slf.request.type_,
+                    actual: res.type_,
+                });
+            }
+            if res.format != T::XCB_BITS {
+                return Err(XcbGetPropertyError::InvalidPropertyFormat {
+                    expected: T::XCB_BITS,
+                    actual: res.format,
+                });
+            }
+            buf.extend_from_slice(&property_value(xcb, res)[..len]);
+            Ok(())
+        })
+    }
+
+    pub unsafe fn discard(self, xcb: &Xcb, c: *mut xcb_connection_t) {
+        let slf = ManuallyDrop::new(self);
+        xcb.xcb_discard_reply(c, slf.request.cookie.sequence);
+    }
+}
+
+impl<T> Drop for XcbPendingProperty<T> {
+    fn drop(&mut self) {
+        panic!("XcbPendingProperty was not handled. You must call `reply` or `discard` instead of dropping this type.");
+    }
+}
+
+/// Sends the first request of a property read without waiting for the reply.
+///
+/// Further requests required to read the remainder of a large property are sent when the
+/// reply is retrieved.
+pub unsafe fn request_property<T: XcbDataType>(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+    delete: bool,
+    step: u32,
+) -> XcbPendingProperty<T> {
+    XcbPendingProperty {
+        request: PropertyRequest::send(xcb, c, window, property, type_, delete, step),
+        _phantom: PhantomData,
+    }
+}
+
 pub unsafe fn get_property<T: XcbDataType>(
     xcb: &Xcb,
     errors: &XcbErrorParser,
@@ -44,47 +187,449 @@ pub unsafe fn get_property_in<T: XcbDataType>(
     step: u32,
     buf: &mut Vec<T>,
 ) -> Result<(), XcbGetPropertyError> {
-    let mut offset = 0;
-    loop {
-        let mut err = ptr::null_mut();
-        let res = xcb.xcb_get_property_reply(
-            errors.c,
-            xcb.xcb_get_property(
-                errors.c,
-                delete as u8,
-                window,
-                property,
-                type_,
-                offset,
-                step,
-            ),
-            &mut err,
-        );
-        let res = errors.check(xcb, res, err)?;
-        if res.type_ != type_ {
+    request_property(xcb, errors.c, window, property, type_, delete, step)
+        .reply_in(xcb, errors, buf)
+}
+
+pub unsafe fn get_property_limited<T: XcbDataType>(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+    delete: bool,
+    step: u32,
+    limit: XcbPropertyLimit,
+) -> Result<XcbLimitedProperty<Vec<T>>, XcbGetPropertyError> {
+    request_property(xcb, errors.c, window, property, type_, delete, step)
+        .reply_limited(xcb, errors, limit)
+}
+
+/// A limit that truncates a 32-bit property after `len` elements.
+pub(crate) fn truncate_after(len: usize) -> XcbPropertyLimit {
+    XcbPropertyLimit {
+        max_bytes: (len * 4) as u32,
+        policy: XcbPropertyLimitPolicy::Truncate,
+    }
+}
+
+/// Reads at most `len` elements of a property. Like Xlib, longer values are truncated.
+pub(crate) unsafe fn get_u32_property(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+    len: usize,
+) -> Result<Vec<u32>, XcbGetPropertyError> {
+    let value = get_property_limited(
+        xcb,
+        errors,
+        window,
+        property,
+        type_,
+        false,
+        len as u32,
+        truncate_after(len),
+    )?;
+    Ok(value.value)
+}
+
+/// Reads a single 32-bit value. An empty property is treated as unset.
+pub(crate) unsafe fn get_u32_value(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+) -> Result<u32, XcbGetPropertyError> {
+    let value = get_u32_property(xcb, errors, window, property, type_, 1)?;
+    value.first().copied().ok_or(XcbGetPropertyError::Unset)
+}
+
+/// The elements of a property whose format was not known in advance.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum XcbPropertyValue {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+/// A property read with `AnyPropertyType`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XcbAnyProperty {
+    pub type_: xcb_atom_t,
+    pub value: XcbPropertyValue,
+}
+
+#[must_use = "XcbPendingAnyProperty panics when dropped."]
+pub struct XcbPendingAnyProperty {
+    request: PropertyRequest,
+}
+
+impl XcbPendingAnyProperty {
+    pub unsafe fn reply(
+        self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+    ) -> Result<XcbAnyProperty, XcbGetPropertyError> {
+        self.read(xcb, errors, None).map(|p| p.value)
+    }
+
+    pub unsafe fn reply_limited(
+        self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        limit: XcbPropertyLimit,
+    ) -> Result<XcbLimitedProperty<XcbAnyProperty>, XcbGetPropertyError> {
+        self.read(xcb, errors, Some(limit))
+    }
+
+    unsafe fn read(
+        self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        limit: Option<XcbPropertyLimit>,
+    ) -> Result<XcbLimitedProperty<XcbAnyProperty>, XcbGetPropertyError> {
+        let slf = ManuallyDrop::new(self);
+        let mut res: Option<XcbAnyProperty> = None;
+        let truncated = slf.request.read(xcb, errors, limit, |reply, len| {
+            let prop = match &mut res {
+                Some(prop) => prop,
+                None => {
+                    let value = match reply.format {
+                        8 => XcbPropertyValue::U8(vec![]),
+                        16 => XcbPropertyValue::U16(vec![]),
+                        32 => XcbPropertyValue::U32(vec![]),
+                        _ => {
+                            return Err(XcbGetPropertyError::InvalidPropertyFormat {
+                                expected: 32,
+                                actual: reply.format,
+                            })
+                        }
+                    };
+                    res.insert(XcbAnyProperty {
+                        type_: reply.type_,
+                        value,
+                    })
+                }
+            };
+            // The property might have been replaced between two chunks.
+            if reply.type_ != prop.type_ {
+                return Err(XcbGetPropertyError::InvalidPropertyType {
+                    expected: prop.type_,
+                    actual: reply.type_,
+                });
+            }
+            let expected = prop.value.format();
+            match &mut prop.value {
+                XcbPropertyValue::U8(v) if reply.format == 8 => {
+                    v.extend_from_slice(&property_value(xcb, reply)[..len])
+                }
+                XcbPropertyValue::U16(v) if reply.format == 16 => {
+                    v.extend_from_slice(&property_value(xcb, reply)[..len])
+                }
+                XcbPropertyValue::U32(v) if reply.format == 32 => {
+                    v.extend_from_slice(&property_value(xcb, reply)[..len])
+                }
+                _ => {
+                    return Err(XcbGetPropertyError::InvalidPropertyFormat {
+                        expected,
+                        actual: reply.format,
+                    })
+                }
+            }
+            Ok(())
+        })?;
+        Ok(XcbLimitedProperty {
+            value: res.unwrap(),
+            truncated,
+        })
+    }
+
+    pub unsafe fn discard(self, xcb: &Xcb, c: *mut xcb_connection_t) {
+        let slf = ManuallyDrop::new(self);
+        xcb.xcb_discard_reply(c, slf.request.cookie.sequence);
+    }
+}
+
+impl Drop for XcbPendingAnyProperty {
+    fn drop(&mut self) {
+        panic!("XcbPendingAnyProperty was not handled. You must call `reply` or `discard` instead of dropping this type.");
+    }
+}
+
+impl XcbPropertyValue {
+    /// The number of bits in each element.
+    pub fn format(&self) -> u8 {
+        match self {
+            XcbPropertyValue::U8(_) => 8,
+            XcbPropertyValue::U16(_) => 16,
+            XcbPropertyValue::U32(_) => 32,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            XcbPropertyValue::U8(v) => v.len(),
+            XcbPropertyValue::U16(v) => v.len(),
+            XcbPropertyValue::U32(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub unsafe fn request_any_property(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    delete: bool,
+    step: u32,
+) -> XcbPendingAnyProperty {
+    XcbPendingAnyProperty {
+        request: PropertyRequest::send(
+            xcb,
+            c,
+            window,
+            property,
+            XCB_GET_PROPERTY_TYPE_ANY,
+            delete,
+            step,
+        ),
+    }
+}
+
+pub unsafe fn get_any_property(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    delete: bool,
+    step: u32,
+) -> Result<XcbAnyProperty, XcbGetPropertyError> {
+    request_any_property(xcb, errors.c, window, property, delete, step).reply(xcb, errors)
+}
+
+pub unsafe fn get_any_property_limited(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    delete: bool,
+    step: u32,
+    limit: XcbPropertyLimit,
+) -> Result<XcbLimitedProperty<XcbAnyProperty>, XcbGetPropertyError> {
+    request_any_property(xcb, errors.c, window, property, delete, step)
+        .reply_limited(xcb, errors, limit)
+}
+
+struct PropertyRequest {
+    cookie: xcb_get_property_cookie_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+    delete: bool,
+    step: u32,
+}
+
+impl PropertyRequest {
+    unsafe fn send(
+        xcb: &Xcb,
+        c: *mut xcb_connection_t,
+        window: xcb_window_t,
+        property: xcb_atom_t,
+        type_: xcb_atom_t,
+        delete: bool,
+        step: u32,
+    ) -> Self {
+        Self {
+            cookie: xcb.xcb_get_property(c, delete as u8, window, property, type_, 0, step),
+            window,
+            property,
+            type_,
+            delete,
+            step,
+        }
+    }
+
+    /// Retrieves the reply and requests further chunks until the whole property has been
+    /// read. `f` is called for every reply of a set property together with the number of
+    /// elements of the reply that should be used.
+    ///
+    /// Returns whether the property was truncated.
+    unsafe fn read<F>(
+        &self,
+        xcb: &Xcb,
+        errors: &XcbErrorParser,
+        limit: Option<XcbPropertyLimit>,
+        mut f: F,
+    ) -> Result<bool, XcbGetPropertyError>
+    where
+        F: FnMut(&xcb_get_property_reply_t, usize) -> Result<(), XcbGetPropertyError>,
+    {
+        let mut cookie = self.cookie;
+        let mut received = 0;
+        loop {
+            let mut err = ptr::null_mut();
+            let res = xcb.xcb_get_property_reply(errors.c, cookie, &mut err);
+            let res = errors.check(xcb, res, err)?;
             if res.type_ == 0 {
                 return Err(XcbGetPropertyError::Unset);
             }
-            return Err(XcbGetPropertyError::InvalidPropertyType {
-                expected: type_,
-                actual: res.type_,
-            });
+            let element_size = (res.format as u64 / 8).max(1);
+            let value_size = res.value_len as u64 * element_size;
+            let size = received + value_size + res.bytes_after as u64;
+            let exceeded = limit.filter(|l| size > l.max_bytes as u64);
+            let mut len = res.value_len as usize;
+            if let Some(limit) = exceeded {
+                match limit.policy {
+                    XcbPropertyLimitPolicy::Error => {
+                        // Let `f` validate the type and format before reporting the size.
+                        f(&res, 0)?;
+                        return Err(XcbGetPropertyError::TooLarge {
+                            limit: limit.max_bytes,
+                            size,
+                        });
+                    }
+                    XcbPropertyLimitPolicy::Truncate => {
+                        let fits = (limit.max_bytes as u64 - received) / element_size;
+                        len = len.min(fits as usize);
+                    }
+                }
+            }
+            f(&res, len)?;
+            received += len as u64 * element_size;
+            if res.bytes_after == 0 || len < res.value_len as usize {
+                return Ok(exceeded.is_some());
+            }
+            if let Some(limit) = exceeded {
+                if limit.max_bytes as u64 - received < element_size {
+                    return Ok(true);
+                }
+            }
+            let mut length = self.step;
+            if let Some(limit) = limit {
+                let remaining = (limit.max_bytes as u64 - received).div_ceil(4);
+                length = length.min(remaining as u32);
+            }
+            cookie = xcb.xcb_get_property(
+                errors.c,
+                self.delete as u8,
+                self.window,
+                self.property,
+                self.type_,
+                (received / 4) as u32,
+                length,
+            );
         }
-        if res.format != T::XCB_BITS {
-            return Err(XcbGetPropertyError::InvalidPropertyFormat {
-                expected: T::XCB_BITS,
-                actual: res.format,
-            });
+    }
+}
+
+unsafe fn property_value<'a, T: XcbDataType>(
+    xcb: &Xcb,
+    res: &'a xcb_get_property_reply_t,
+) -> &'a [T] {
+    let value = xcb.xcb_get_property_value(res);
+    slice::from_raw_parts(value as *const T, res.value_len as usize)
+}
+
+/// Replaces the contents of a property.
+///
+/// If the value does not fit into a single request, the first part replaces the property
+/// and the remainder is appended in further requests. Other clients can observe the
+/// intermediate states.
+pub unsafe fn set_property<T: XcbDataType>(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+    data: &[T],
+) -> XcbPendingCommands {
+    change_property(xcb, c, XCB_PROP_MODE_REPLACE, window, property, type_, data)
+}
+
+pub unsafe fn append_property<T: XcbDataType>(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+    data: &[T],
+) -> XcbPendingCommands {
+    change_property(xcb, c, XCB_PROP_MODE_APPEND, window, property, type_, data)
+}
+
+pub unsafe fn prepend_property<T: XcbDataType>(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+    data: &[T],
+) -> XcbPendingCommands {
+    change_property(xcb, c, XCB_PROP_MODE_PREPEND, window, property, type_, data)
+}
+
+pub unsafe fn delete_property(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+) -> XcbPendingCommand {
+    xcb.xcb_delete_property_checked(c, window, property).into()
+}
+
+unsafe fn change_property<T: XcbDataType>(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    mode: xcb_prop_mode_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    type_: xcb_atom_t,
+    data: &[T],
+) -> XcbPendingCommands {
+    let mut commands = XcbPendingCommands::new();
+    let mut send = |mode: xcb_prop_mode_t, data: &[T]| {
+        let cookie = xcb.xcb_change_property_checked(
+            c,
+            mode as u8,
+            window,
+            property,
+            type_,
+            T::XCB_BITS,
+            data.len() as u32,
+            data.as_ptr() as *const _,
+        );
+        commands.push(cookie.into());
+    };
+    let max_len = max_property_len::<T>(xcb, c);
+    if data.len() <= max_len {
+        send(mode, data);
+    } else if mode == XCB_PROP_MODE_PREPEND {
+        for chunk in data.chunks(max_len).rev() {
+            send(XCB_PROP_MODE_PREPEND, chunk);
         }
-        let value = xcb.xcb_get_property_value(&*res);
-        buf.extend_from_slice(slice::from_raw_parts(
-            value as *const T,
-            res.value_len as usize,
-        ));
-        if res.bytes_after == 0 {
-            break;
+    } else {
+        let mut mode = mode;
+        for chunk in data.chunks(max_len) {
+            send(mode, chunk);
+            mode = XCB_PROP_MODE_APPEND;
         }
-        offset += step;
     }
-    Ok(())
+    commands
+}
+
+/// The maximum number of elements that fit into a single ChangeProperty request.
+unsafe fn max_property_len<T: XcbDataType>(xcb: &Xcb, c: *mut xcb_connection_t) -> usize {
+    // The request header is 6 words. Another word is required if the length has to be
+    // encoded via BIG-REQUESTS.
+    const HEADER_LEN: usize = 7;
+    let max_request_len = xcb.xcb_get_maximum_request_length(c) as usize;
+    let max_bytes = max_request_len.saturating_sub(HEADER_LEN) * 4;
+    (max_bytes / mem::size_of::<T>()).max(1)
 }
@@ -0,0 +1,222 @@
+use crate::error::XcbErrorParser;
+use crate::event::send_event;
+use crate::hint::XcbHints;
+use crate::property::{
+    request_property, set_property, XcbGetPropertyError, XcbPropertyLimit, XcbPropertyLimitPolicy,
+};
+use crate::void::{XcbPendingCommand, XcbPendingCommands};
+use std::convert::TryFrom;
+use std::mem;
+use xcb_dl::ffi::*;
+use xcb_dl::Xcb;
+
+/// The number of protocols that are read. Further protocols are ignored.
+const PROTOCOLS_LIMIT: XcbPropertyLimit = XcbPropertyLimit {
+    max_bytes: 64 * 4,
+    policy: XcbPropertyLimitPolicy::Truncate,
+};
+
+crate::atoms! {
+    /// The atoms used by the WM_PROTOCOLS protocols.
+    pub struct XcbProtocolAtoms {
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        WM_TAKE_FOCUS,
+        _NET_WM_PING,
+        _NET_WM_SYNC_REQUEST,
+    }
+}
+
+/// A decoded WM_PROTOCOLS client message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbProtocolMessage {
+    DeleteWindow,
+    TakeFocus {
+        timestamp: xcb_timestamp_t,
+    },
+    Ping {
+        timestamp: xcb_timestamp_t,
+        window: xcb_window_t,
+    },
+    SyncRequest {
+        timestamp: xcb_timestamp_t,
+        value: i64,
+    },
+    Other(xcb_atom_t),
+}
+
+impl XcbProtocolMessage {
+    /// Decodes a client message. Returns `None` if it is not a WM_PROTOCOLS message.
+    pub fn decode(atoms: &XcbProtocolAtoms, event: &xcb_client_message_event_t) -> Option<Self> {
+        if event.type_ != atoms.WM_PROTOCOLS || event.format != 32 {
+            return None;
+        }
+        let data = unsafe { event.data.data32 };
+        let protocol = data[0];
+        let timestamp = data[1];
+        let msg = if protocol == atoms.WM_DELETE_WINDOW {
+            XcbProtocolMessage::DeleteWindow
+        } else if protocol == atoms.WM_TAKE_FOCUS {
+            XcbProtocolMessage::TakeFocus { timestamp }
+        } else if protocol == atoms._NET_WM_PING {
+            XcbProtocolMessage::Ping {
+                timestamp,
+                window: data[2],
+            }
+        } else if protocol == atoms._NET_WM_SYNC_REQUEST {
+            XcbProtocolMessage::SyncRequest {
+                timestamp,
+                value: ((data[3] as i32 as i64) << 32) | data[2] as i64,
+            }
+        } else {
+            XcbProtocolMessage::Other(protocol)
+        };
+        Some(msg)
+    }
+}
+
+/// Answers a _NET_WM_PING message by sending it back to the root window.
+pub unsafe fn pong(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    root: xcb_window_t,
+    event: &xcb_client_message_event_t,
+) -> XcbPendingCommand {
+    let mut event = *event;
+    event.response_type = XCB_CLIENT_MESSAGE;
+    event.window = root;
+    send_event(
+        xcb,
+        c,
+        false,
+        root,
+        XCB_EVENT_MASK_SUBSTRUCTURE_NOTIFY | XCB_EVENT_MASK_SUBSTRUCTURE_REDIRECT,
+        &event,
+    )
+}
+
+/// Decodes a client message and answers _NET_WM_PING messages.
+///
+/// Errors that occur while answering a ping are ignored.
+pub unsafe fn handle_client_message(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbProtocolAtoms,
+    root: xcb_window_t,
+    event: &xcb_client_message_event_t,
+) -> Option<XcbProtocolMessage> {
+    let msg = XcbProtocolMessage::decode(atoms, event)?;
+    if let XcbProtocolMessage::Ping { .. } = msg {
+        // The window field is the root window once the message has been answered.
+        if event.window != root {
+            pong(xcb, c, root, event).discard(xcb, c);
+        }
+    }
+    Some(msg)
+}
+
+pub unsafe fn get_wm_protocols(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbProtocolAtoms,
+    window: xcb_window_t,
+) -> Result<Vec<xcb_atom_t>, XcbGetPropertyError> {
+    let protocols = request_property(
+        xcb,
+        errors.c,
+        window,
+        atoms.WM_PROTOCOLS,
+        XCB_ATOM_ATOM,
+        false,
+        16,
+    )
+    .reply_limited(xcb, errors, PROTOCOLS_LIMIT)?;
+    Ok(protocols.value)
+}
+
+pub unsafe fn set_wm_protocols(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbProtocolAtoms,
+    window: xcb_window_t,
+    protocols: &[xcb_atom_t],
+) -> XcbPendingCommands {
+    set_property(xcb, c, window, atoms.WM_PROTOCOLS, XCB_ATOM_ATOM, protocols)
+}
+
+/// The ICCCM input model of a window.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbInputModel {
+    /// The window never expects keyboard input.
+    NoInput,
+    /// The window expects the window manager to set the input focus.
+    Passive,
+    /// The window expects the window manager to set the input focus and sets the focus to
+    /// its other windows itself.
+    LocallyActive,
+    /// The window sets the input focus itself when it receives WM_TAKE_FOCUS.
+    GloballyActive,
+}
+
+impl XcbInputModel {
+    /// Classifies a window from its WM_HINTS and whether WM_PROTOCOLS contains
+    /// WM_TAKE_FOCUS.
+    ///
+    /// If the input hint is not set, the window is assumed to expect input.
+    pub fn new(hints: Option<&XcbHints>, take_focus: bool) -> Self {
+        let input = hints.and_then(|h| h.input()) != Some(0);
+        match (input, take_focus) {
+            (false, false) => XcbInputModel::NoInput,
+            (true, false) => XcbInputModel::Passive,
+            (true, true) => XcbInputModel::LocallyActive,
+            (false, true) => XcbInputModel::GloballyActive,
+        }
+    }
+}
+
+/// Reads WM_HINTS and WM_PROTOCOLS with a single round trip and classifies the window.
+///
+/// Unset or invalid properties are treated as if they contained no hints.
+pub unsafe fn get_input_model(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbProtocolAtoms,
+    window: xcb_window_t,
+) -> Result<XcbInputModel, XcbGetPropertyError> {
+    let hints = request_property::<u32>(
+        xcb,
+        errors.c,
+        window,
+        XCB_ATOM_WM_HINTS,
+        XCB_ATOM_WM_HINTS,
+        false,
+        16,
+    );
+    let protocols = request_property::<u32>(
+        xcb,
+        errors.c,
+        window,
+        atoms.WM_PROTOCOLS,
+        XCB_ATOM_ATOM,
+        false,
+        16,
+    );
+    let limit = XcbPropertyLimit {
+        max_bytes: mem::size_of::<XcbHints>() as u32,
+        policy: XcbPropertyLimitPolicy::Truncate,
+    };
+    let hints = hints.reply_limited(xcb, errors, limit);
+    let protocols = protocols.reply_limited(xcb, errors, PROTOCOLS_LIMIT);
+    let hints = match hints {
+        Ok(h) => XcbHints::try_from(&h.value[..]).ok(),
+        Err(XcbGetPropertyError::Xcb(e)) => return Err(e.into()),
+        Err(_) => None,
+    };
+    let protocols = match protocols {
+        Ok(p) => p.value,
+        Err(XcbGetPropertyError::Xcb(e)) => return Err(e.into()),
+        Err(_) => vec![],
+    };
+    let take_focus = protocols.contains(&atoms.WM_TAKE_FOCUS);
+    Ok(XcbInputModel::new(hints.as_ref(), take_focus))
+}
@@ -0,0 +1,361 @@
+//! The _NET_WM_SYNC_REQUEST protocol.
+//!
+//! The window manager sends a sync request before it configures a window. The client
+//! updates its counter once it has redrawn the window at the new size. With the extended
+//! counter, the client additionally marks each frame it draws by making the counter odd
+//! while drawing and even once the frame is complete.
+
+use crate::error::{XcbError, XcbErrorParser};
+use crate::event::send_client_message;
+use crate::property::{get_u32_property, set_property, XcbGetPropertyError};
+use crate::void::{XcbPendingCommand, XcbPendingCommands};
+use std::ptr;
+use xcb_dl::ffi::*;
+use xcb_dl::{Xcb, XcbSync};
+
+crate::atoms! {
+    /// The atoms used by the _NET_WM_SYNC_REQUEST protocol.
+    pub struct XcbSyncAtoms {
+        WM_PROTOCOLS,
+        _NET_WM_SYNC_REQUEST,
+        _NET_WM_SYNC_REQUEST_COUNTER,
+    }
+}
+
+pub fn to_sync_int64(v: i64) -> xcb_sync_int64_t {
+    xcb_sync_int64_t {
+        hi: (v >> 32) as i32,
+        lo: v as u32,
+    }
+}
+
+pub fn from_sync_int64(v: xcb_sync_int64_t) -> i64 {
+    ((v.hi as i64) << 32) | v.lo as i64
+}
+
+/// The counters in the _NET_WM_SYNC_REQUEST_COUNTER property.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct XcbSyncCounters {
+    pub basic: xcb_sync_counter_t,
+    pub extended: Option<xcb_sync_counter_t>,
+}
+
+pub unsafe fn get_sync_request_counters(
+    xcb: &Xcb,
+    errors: &XcbErrorParser,
+    atoms: &XcbSyncAtoms,
+    window: xcb_window_t,
+) -> Result<XcbSyncCounters, XcbGetPropertyError> {
+    let value = get_u32_property(
+        xcb,
+        errors,
+        window,
+        atoms._NET_WM_SYNC_REQUEST_COUNTER,
+        XCB_ATOM_CARDINAL,
+        2,
+    )?;
+    match value[..] {
+        [basic] => Ok(XcbSyncCounters {
+            basic,
+            extended: None,
+        }),
+        [basic, extended, ..] => Ok(XcbSyncCounters {
+            basic,
+            extended: Some(extended),
+        }),
+        _ => Err(XcbGetPropertyError::Unset),
+    }
+}
+
+pub unsafe fn set_sync_request_counters(
+    xcb: &Xcb,
+    c: *mut xcb_connection_t,
+    atoms: &XcbSyncAtoms,
+    window: xcb_window_t,
+    counters: &XcbSyncCounters,
+) -> XcbPendingCommands {
+    let mut value = vec![counters.basic];
+    value.extend(counters.extended);
+    set_property(
+        xcb,
+        c,
+        window,
+        atoms._NET_WM_SYNC_REQUEST_COUNTER,
+        XCB_ATOM_CARDINAL,
+        &value,
+    )
+}
+
+/// The client side of the protocol.
+///
+/// The client must also add _NET_WM_SYNC_REQUEST to its WM_PROTOCOLS. Sync requests are
+/// decoded by `protocol::XcbProtocolMessage`.
+#[derive(Debug)]
+pub struct XcbSyncClient {
+    counters: XcbSyncCounters,
+    /// The value requested by the window manager that has not yet been acknowledged.
+    pending: Option<i64>,
+    /// The current value of the extended counter.
+    extended_value: i64,
+}
+
+impl XcbSyncClient {
+    /// Creates the counters. The extended counter is only created if `extended` is true.
+    pub unsafe fn new(
+        xcb: &Xcb,
+        sync: &XcbSync,
+        c: *mut xcb_connection_t,
+        extended: bool,
+    ) -> (Self, XcbPendingCommands) {
+        let mut commands = XcbPendingCommands::new();
+        let mut create = || {
+            let counter = xcb.xcb_generate_id(c);
+            commands.push(
+                sync.xcb_sync_create_counter_checked(c, counter, to_sync_int64(0))
+                    .into(),
+            );
+            counter
+        };
+        let counters = XcbSyncCounters {
+            basic: create(),
+            extended: if extended { Some(create()) } else { None },
+        };
+        let slf = Self {
+            counters,
+            pending: None,
+            extended_value: 0,
+        };
+        (slf, commands)
+    }
+
+    pub fn counters(&self) -> &XcbSyncCounters {
+        &self.counters
+    }
+
+    /// Advertises the counters in _NET_WM_SYNC_REQUEST_COUNTER.
+    pub unsafe fn advertise(
+        &self,
+        xcb: &Xcb,
+        c: *mut xcb_connection_t,
+        atoms: &XcbSyncAtoms,
+        window: xcb_window_t,
+    ) -> XcbPendingCommands {
+        set_sync_request_counters(xcb, c, atoms, window, &self.counters)
+    }
+
+    /// Records a sync request. The value is acknowledged by the next call to `end_frame`.
+    pub fn handle_request(&mut self, value: i64) {
+        self.pending = Some(value);
+    }
+
+    /// Marks the start of a frame by making the extended counter odd.
+    ///
+    /// Returns `None` if there is no extended counter or if a frame is already in
+    /// progress.
+    pub unsafe fn begin_frame(
+        &mut self,
+        sync: &XcbSync,
+        c: *mut xcb_connection_t,
+    ) -> Option<XcbPendingCommand> {
+        let counter = self.counters.extended?;
+        if self.extended_value % 2 != 0 {
+            return None;
+        }
+        self.extended_value += 1;
+        let value = to_sync_int64(self.extended_value);
+        Some(sync.xcb_sync_set_counter_checked(c, counter, value).into())
+    }
+
+    /// Marks the end of a frame and acknowledges the pending sync request, if any.
+    ///
+    /// The basic counter is set to the requested value. The extended counter is set to the
+    /// requested value or, if there is no request or the request is outdated, to the next
+    /// even value.
+    pub unsafe fn end_frame(
+        &mut self,
+        sync: &XcbSync,
+        c: *mut xcb_connection_t,
+    ) -> XcbPendingCommands {
+        let mut commands = XcbPendingCommands::new();
+        let pending = self.pending.take();
+        // Window managers that do not support the extended counter use the basic counter
+        // even if the client provides both.
+        if let Some(v) = pending {
+            let value = to_sync_int64(v);
+            commands.push(
+                sync.xcb_sync_set_counter_checked(c, self.counters.basic, value)
+                    .into(),
+            );
+        }
+        if let Some(counter) = self.counters.extended {
+            let next = self.extended_value + self.extended_value.rem_euclid(2);
+            self.extended_value = match pending {
+                Some(v) if v > next => v + v.rem_euclid(2),
+                _ if next == self.extended_value => next + 2,
+                _ => next,
+            };
+            let value = to_sync_int64(self.extended_value);
+            commands.push(sync.xcb_sync_set_counter_checked(c, counter, value).into());
+        }
+        commands
+    }
+
+    pub unsafe fn destroy(self, sync: &XcbSync, c: *mut xcb_connection_t) -> XcbPendingCommands {
+        let mut commands = XcbPendingCommands::new();
+        let counters = [Some(self.counters.basic), self.counters.extended];
+        for counter in counters.iter().flatten() {
+            commands.push(sync.xcb_sync_destroy_counter_checked(c, *counter).into());
+        }
+        commands
+    }
+}
+
+/// The window manager side of the protocol for a single window.
+#[derive(Debug)]
+pub struct XcbSyncWm {
+    counters: XcbSyncCounters,
+    /// The value of the last sync request.
+    value: i64,
+    /// The last value of the counter that was observed.
+    observed: i64,
+}
+
+/// How far ahead of the observed value of the extended counter sync requests are placed.
+///
+/// The client keeps incrementing the extended counter for the frames it draws until it has
+/// handled the request. The request value must stay ahead of these frames.
+const EXTENDED_STEP: i64 = 240;
+
+impl XcbSyncWm {
+    /// `initial` is the current value of the counter that will be used.
+    pub fn new(counters: XcbSyncCounters, initial: i64) -> Self {
+        Self {
+            counters,
+            value: initial,
+            observed: initial,
+        }
+    }
+
+    /// Returns the counter that the window manager waits on. This is the extended counter
+    /// if the client provides one.
+    pub fn counter(&self) -> xcb_sync_counter_t {
+        self.counters.extended.unwrap_or(self.counters.basic)
+    }
+
+    pub fn is_extended(&self) -> bool {
+        self.counters.extended.is_some()
+    }
+
+    /// Records the current value of the counter.
+    ///
+    /// The next sync request is placed ahead of this value.
+    pub fn observe(&mut self, counter_value: i64) {
+        self.observed = counter_value;
+    }
+
+    /// Records the counter value of an AlarmNotify event of an alarm on the counter.
+    pub fn handle_alarm_notify(&mut self, event: &xcb_sync_alarm_notify_event_t) {
+        self.observe(from_sync_int64(event.counter_value));
+    }
+
+    /// Queries and records the current value of the counter.
+    pub unsafe fn query_counter(
+        &mut self,
+        xcb: &Xcb,
+        sync: &XcbSync,
+        errors: &XcbErrorParser,
+    ) -> Result<i64, XcbError> {
+        let mut err = ptr::null_mut();
+        let reply = sync.xcb_sync_query_counter_reply(
+            errors.c,
+            sync.xcb_sync_query_counter(errors.c, self.counter()),
+            &mut err,
+        );
+        let reply = errors.check(xcb, reply, err)?;
+        let value = from_sync_int64(reply.counter_value);
+        self.observe(value);
+        Ok(value)
+    }
+
+    /// Sends a sync request. This should be done before configuring the window.
+    ///
+    /// The request value is placed ahead of both the last request and the observed
+    /// counter value. For the extended counter, it leaves room for the frames that the
+    /// client draws in the meantime.
+    ///
+    /// Returns the value that the counter will reach once the client has redrawn the
+    /// window. For the extended counter, this value is even.
+    pub unsafe fn send_request(
+        &mut self,
+        xcb: &Xcb,
+        c: *mut xcb_connection_t,
+        atoms: &XcbSyncAtoms,
+        window: xcb_window_t,
+        timestamp: xcb_timestamp_t,
+    ) -> (i64, XcbPendingCommand) {
+        let base = self.value.max(self.observed);
+        self.value = match self.is_extended() {
+            true => {
+                let value = base + EXTENDED_STEP;
+                value + value.rem_euclid(2)
+            }
+            false => base + 1,
+        };
+        let value = to_sync_int64(self.value);
+        let data = [
+            atoms._NET_WM_SYNC_REQUEST,
+            timestamp,
+            value.lo,
+            value.hi as u32,
+            self.is_extended() as u32,
+        ];
+        let command = send_client_message(
+            xcb,
+            c,
+            window,
+            XCB_EVENT_MASK_NO_EVENT,
+            window,
+            atoms.WM_PROTOCOLS,
+            data,
+        );
+        (self.value, command)
+    }
+
+    /// Returns whether the counter value shows that the last request has been handled.
+    ///
+    /// For the extended counter, the value must also be even since odd values mark
+    /// frames in progress.
+    pub fn is_done(&self, counter_value: i64) -> bool {
+        if self.is_extended() && counter_value % 2 != 0 {
+            return false;
+        }
+        counter_value >= self.value
+    }
+
+    /// Creates an alarm that generates an AlarmNotify event once the last request has
+    /// been handled.
+    pub unsafe fn create_alarm(
+        &self,
+        xcb: &Xcb,
+        sync: &XcbSync,
+        c: *mut xcb_connection_t,
+    ) -> (xcb_sync_alarm_t, XcbPendingCommand) {
+        let alarm = xcb.xcb_generate_id(c);
+        let values = xcb_sync_create_alarm_value_list_t {
+            counter: self.counter(),
+            value_type: XCB_SYNC_VALUETYPE_ABSOLUTE,
+            value: to_sync_int64(self.value),
+            test_type: XCB_SYNC_TESTTYPE_POSITIVE_COMPARISON,
+            delta: to_sync_int64(0),
+            events: 1,
+        };
+        let mask = XCB_SYNC_CA_COUNTER
+            | XCB_SYNC_CA_VALUE_TYPE
+            | XCB_SYNC_CA_VALUE
+            | XCB_SYNC_CA_TEST_TYPE
+            | XCB_SYNC_CA_DELTA
+            | XCB_SYNC_CA_EVENTS;
+        let command = sync.xcb_sync_create_alarm_aux_checked(c, alarm, mask, &values);
+        (alarm, command.into())
+    }
+}
@@ -0,0 +1,480 @@
+//! Conversion between Rust strings and the encodings of text properties.
+//!
+//! COMPOUND_TEXT is decoded for ASCII, the ISO 8859-1 and ISO 8859-5 right halves,
+//! JIS X0201 Katakana, and UTF-8 segments. Characters from other character sets are
+//! replaced by U+FFFD.
+
+use bstr::BString;
+use thiserror::Error;
+use xcb_dl::ffi::*;
+
+const ESC: u8 = 0x1b;
+const CSI: u8 = 0x9b;
+
+crate::atoms! {
+    /// The atoms of the text encodings that are not predefined.
+    pub struct XcbTextAtoms {
+        UTF8_STRING,
+        COMPOUND_TEXT,
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum XcbTextEncoding {
+    /// ISO 8859-1.
+    String,
+    Utf8String,
+    CompoundText,
+}
+
+impl XcbTextEncoding {
+    pub fn from_atom(atoms: &XcbTextAtoms, atom: xcb_atom_t) -> Option<Self> {
+        if atom == XCB_ATOM_STRING {
+            Some(XcbTextEncoding::String)
+        } else if atom == atoms.UTF8_STRING {
+            Some(XcbTextEncoding::Utf8String)
+        } else if atom == atoms.COMPOUND_TEXT {
+            Some(XcbTextEncoding::CompoundText)
+        } else {
+            None
+        }
+    }
+
+    pub fn atom(self, atoms: &XcbTextAtoms) -> xcb_atom_t {
+        match self {
+            XcbTextEncoding::String => XCB_ATOM_STRING,
+            XcbTextEncoding::Utf8String => atoms.UTF8_STRING,
+            XcbTextEncoding::CompoundText => atoms.COMPOUND_TEXT,
+        }
+    }
+
+    /// Decodes a single string. Invalid sequences are replaced by U+FFFD.
+    pub fn decode(self, b: &[u8]) -> String {
+        match self {
+            XcbTextEncoding::String => decode_latin1(b),
+            XcbTextEncoding::Utf8String => String::from_utf8_lossy(b).into_owned(),
+            XcbTextEncoding::CompoundText => decode_compound_text(b),
+        }
+    }
+
+    /// Encodes a single string.
+    ///
+    /// Returns `None` if the encoding is STRING and the string is not representable in it.
+    pub fn encode(self, s: &str) -> Option<Vec<u8>> {
+        match self {
+            XcbTextEncoding::String => encode_latin1(s),
+            XcbTextEncoding::Utf8String => Some(s.as_bytes().to_vec()),
+            XcbTextEncoding::CompoundText => Some(encode_compound_text(s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum XcbTextError {
+    #[error("Unsupported text encoding {0}")]
+    UnsupportedEncoding(xcb_atom_t),
+}
+
+/// The contents of a text property together with their encoding.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct XcbTextProperty {
+    /// The type of the property, e.g. STRING, UTF8_STRING, or COMPOUND_TEXT.
+    pub encoding: xcb_atom_t,
+    pub value: BString,
+}
+
+impl XcbTextProperty {
+    /// Encodes a string as STRING if it is representable in ISO 8859-1 and in the
+    /// fallback encoding otherwise.
+    pub fn encode(atoms: &XcbTextAtoms, s: &str, fallback: XcbTextEncoding) -> Self {
+        Self::encode_list(atoms, &[s], fallback)
+    }
+
+    /// Like `encode` but for a list of null-separated strings.
+    ///
+    /// STRING is only used if all strings are representable in it.
+    pub fn encode_list<S: AsRef<str>>(
+        atoms: &XcbTextAtoms,
+        strings: &[S],
+        fallback: XcbTextEncoding,
+    ) -> Self {
+        let encode = |encoding: XcbTextEncoding| {
+            let strings = strings
+                .iter()
+                .map(|s| encoding.encode(s.as_ref()))
+                .collect::<Option<Vec<_>>>()?;
+            let mut value = join_strings(&strings);
+            if strings.len() == 1 {
+                value.pop();
+            }
+            Some(XcbTextProperty {
+                encoding: encoding.atom(atoms),
+                value: value.into(),
+            })
+        };
+        encode(XcbTextEncoding::String)
+            .or_else(|| encode(fallback))
+            .or_else(|| encode(XcbTextEncoding::Utf8String))
+            .unwrap()
+    }
+
+    /// Decodes the value as a single string.
+    pub fn decode(&self, atoms: &XcbTextAtoms) -> Result<String, XcbTextError> {
+        Ok(self.text_encoding(atoms)?.decode(&self.value))
+    }
+
+    /// Decodes the value as a list of null-separated strings.
+    pub fn decode_list(&self, atoms: &XcbTextAtoms) -> Result<Vec<String>, XcbTextError> {
+        let encoding = self.text_encoding(atoms)?;
+        Ok(split_strings(&self.value)
+            .into_iter()
+            .map(|s| encoding.decode(s))
+            .collect())
+    }
+
+    fn text_encoding(&self, atoms: &XcbTextAtoms) -> Result<XcbTextEncoding, XcbTextError> {
+        XcbTextEncoding::from_atom(atoms, self.encoding)
+            .ok_or(XcbTextError::UnsupportedEncoding(self.encoding))
+    }
+}
+
+pub fn decode_latin1(b: &[u8]) -> String {
+    b.iter().map(|&b| b as char).collect()
+}
+
+/// Encodes a string as STRING.
+///
+/// Returns `None` if the string contains characters outside of ISO 8859-1 or control
+/// characters other than newline and tab.
+pub fn encode_latin1(s: &str) -> Option<Vec<u8>> {
+    s.chars()
+        .map(|c| match c as u32 {
+            0x09 | 0x0a | 0x20..=0x7e | 0xa0..=0xff => Some(c as u8),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Charset {
+    Ascii,
+    Latin1,
+    Cyrillic,
+    Katakana,
+    /// A character set that we cannot decode with the given number of bytes per character.
+    Unsupported(usize),
+}
+
+impl Charset {
+    fn decode(self, b: u8) -> char {
+        let b = b & 0x7f;
+        let c = match self {
+            Charset::Ascii => b as u32,
+            Charset::Latin1 => b as u32 | 0x80,
+            Charset::Cyrillic => match b | 0x80 {
+                0xa0 => 0xa0,
+                0xad => 0xad,
+                0xf0 => 0x2116,
+                0xfd => 0xa7,
+                b => 0x401 + (b as u32 - 0xa1),
+            },
+            Charset::Katakana => match b | 0x80 {
+                b @ 0xa1..=0xdf => 0xff61 + (b as u32 - 0xa1),
+                _ => 0xfffd,
+            },
+            Charset::Unsupported(_) => 0xfffd,
+        };
+        char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+
+    fn encode(self, c: char) -> Option<u8> {
+        match (self, c as u32) {
+            (Charset::Latin1, c @ 0xa0..=0xff) => Some(c as u8),
+            (Charset::Cyrillic, c) => match c {
+                0xa0 => Some(0xa0),
+                0xad => Some(0xad),
+                0x2116 => Some(0xf0),
+                0xa7 => Some(0xfd),
+                0x401..=0x40c | 0x40e..=0x44f | 0x451..=0x45c | 0x45e..=0x45f => {
+                    Some((c - 0x401 + 0xa1) as u8)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Decodes COMPOUND_TEXT. Characters that cannot be decoded are replaced by U+FFFD.
+pub fn decode_compound_text(b: &[u8]) -> String {
+    let mut res = String::new();
+    let mut gl = Charset::Ascii;
+    let mut gr = Charset::Latin1;
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        i += 1;
+        match c {
+            ESC => {
+                let start = i;
+                while i < b.len() && (0x20..=0x2f).contains(&b[i]) {
+                    i += 1;
+                }
+                if i >= b.len() {
+                    break;
+                }
+                let intermediate = &b[start..i];
+                let fin = b[i];
+                i += 1;
+                match intermediate {
+                    b"(" => {
+                        gl = if matches!(fin, b'B' | b'J') {
+                            Charset::Ascii
+                        } else {
+                            Charset::Unsupported(1)
+                        }
+                    }
+                    b")" => {
+                        gr = if fin == b'I' {
+                            Charset::Katakana
+                        } else {
+                            Charset::Unsupported(1)
+                        }
+                    }
+                    b"-" => {
+                        gr = match fin {
+                            b'A' => Charset::Latin1,
+                            b'L' => Charset::Cyrillic,
+                            _ => Charset::Unsupported(1),
+                        }
+                    }
+                    b"$" | b"$(" => gl = Charset::Unsupported(2),
+                    b"$)" => gr = Charset::Unsupported(2),
+                    b"%" if fin == b'G' => {
+                        let end = b[i..]
+                            .windows(3)
+                            .position(|w| w == b"\x1b%@")
+                            .map(|p| i + p)
+                            .unwrap_or(b.len());
+                        res.push_str(&String::from_utf8_lossy(&b[i..end]));
+                        i = (end + 3).min(b.len());
+                    }
+                    b"%/" => {
+                        // Extended segment: M L name STX data, where M and L encode the length
+                        // of the remainder.
+                        if i + 2 <= b.len() {
+                            let len = ((b[i] as usize & 0x7f) << 7) | (b[i + 1] as usize & 0x7f);
+                            i = (i + 2 + len).min(b.len());
+                            res.push(char::REPLACEMENT_CHARACTER);
+                        } else {
+                            i = b.len();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            CSI => {
+                // Directionality: CSI 1 ], CSI 2 ], and CSI ].
+                while i < b.len() && (0x20..=0x3f).contains(&b[i]) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'\t' | b'\n' => res.push(c as char),
+            0x20..=0x7f | 0xa0..=0xff => {
+                let set = if c < 0x80 { gl } else { gr };
+                if c == 0x20 {
+                    res.push(' ');
+                } else if let Charset::Unsupported(n) = set {
+                    i = (i + n - 1).min(b.len());
+                    res.push(char::REPLACEMENT_CHARACTER);
+                } else {
+                    res.push(set.decode(c));
+                }
+            }
+            _ => {}
+        }
+    }
+    res
+}
+
+/// Encodes a string as COMPOUND_TEXT.
+///
+/// Characters that are not in ISO 8859-1 or ISO 8859-5 are stored in UTF-8 segments.
+/// Control characters other than newline and tab are dropped.
+pub fn encode_compound_text(s: &str) -> Vec<u8> {
+    let mut res = vec![];
+    let mut gr = Some(Charset::Latin1);
+    let mut utf8 = String::new();
+    let flush = |res: &mut Vec<u8>, utf8: &mut String, gr: &mut Option<Charset>| {
+        if !utf8.is_empty() {
+            res.extend_from_slice(b"\x1b%G");
+            res.extend_from_slice(utf8.as_bytes());
+            res.extend_from_slice(b"\x1b%@");
+            utf8.clear();
+            // Do not rely on the designations surviving the UTF-8 segment.
+            *gr = None;
+        }
+    };
+    for c in s.chars() {
+        if matches!(c, '\t' | '\n' | ' '..='~') {
+            flush(&mut res, &mut utf8, &mut gr);
+            res.push(c as u8);
+            continue;
+        }
+        if c.is_control() {
+            continue;
+        }
+        let mut encoded = false;
+        let mut sets = [(Charset::Latin1, b"\x1b-A"), (Charset::Cyrillic, b"\x1b-L")];
+        // Avoid switching character sets for characters contained in both.
+        if gr == Some(Charset::Cyrillic) {
+            sets.reverse();
+        }
+        for (set, designation) in sets {
+            if let Some(b) = set.encode(c) {
+                flush(&mut res, &mut utf8, &mut gr);
+                if gr != Some(set) {
+                    res.extend_from_slice(designation);
+                    gr = Some(set);
+                }
+                res.push(b);
+                encoded = true;
+                break;
+            }
+        }
+        if !encoded {
+            utf8.push(c);
+        }
+    }
+    flush(&mut res, &mut utf8, &mut gr);
+    res
+}
+
+/// Splits a list of null-terminated strings. The final terminator is optional.
+pub fn split_strings(value: &[u8]) -> Vec<&[u8]> {
+    let value = value.strip_suffix(b"\0").unwrap_or(value);
+    if value.is_empty() {
+        return vec![];
+    }
+    value.split(|b| *b == 0).collect()
+}
+
+/// Joins strings into a list of null-terminated strings.
+pub fn join_strings<S: AsRef<[u8]>>(strings: &[S]) -> Vec<u8> {
+    let mut value = vec![];
+    for s in strings {
+        value.extend_from_slice(s.as_ref());
+        value.push(0);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atoms() -> XcbTextAtoms {
+        XcbTextAtoms {
+            UTF8_STRING: 1000,
+            COMPOUND_TEXT: 1001,
+        }
+    }
+
+    fn round_trip(s: &str) -> Vec<u8> {
+        let encoded = encode_compound_text(s);
+        assert_eq!(decode_compound_text(&encoded), s);
+        encoded
+    }
+
+    #[test]
+    fn latin1() {
+        let s = "héllo\twörld\n¡";
+        let encoded = encode_latin1(s).unwrap();
+        assert_eq!(encoded, b"h\xe9llo\tw\xf6rld\n\xa1");
+        assert_eq!(decode_latin1(&encoded), s);
+        assert_eq!(round_trip(s), encoded);
+        assert_eq!(encode_latin1("ж"), None);
+        assert_eq!(encode_latin1("\x01"), None);
+    }
+
+    #[test]
+    fn cyrillic() {
+        let encoded = round_trip("Ёж № § é");
+        assert_eq!(encoded, b"\x1b-L\xa1\xd6 \xf0 \xfd \x1b-A\xe9");
+    }
+
+    #[test]
+    fn utf8_segments() {
+        let encoded = round_trip("日本 é ж 語");
+        assert!(encoded.starts_with(b"\x1b%G\xe6\x97\xa5\xe6\x9c\xac\x1b%@ "));
+        round_trip("");
+        round_trip("€");
+    }
+
+    #[test]
+    fn decode_designations() {
+        assert_eq!(decode_compound_text(b"\x1b)I\xb1\xb2"), "ｱｲ");
+        assert_eq!(
+            decode_compound_text(b"\x1b(Ja\x1b(Kb \x1b(Bc"),
+            "a\u{fffd} c"
+        );
+        assert_eq!(decode_compound_text(b"\x1b-A\xe9"), "é");
+        assert_eq!(decode_compound_text(b"\x1b$)A\xb0\xa1x"), "\u{fffd}x");
+        assert_eq!(decode_compound_text(b"\x9b1]a\x9b]"), "a");
+        assert_eq!(
+            decode_compound_text(b"\x1b%/1\x80\x86abc\x02de"),
+            "\u{fffd}"
+        );
+        assert_eq!(decode_compound_text(b"\x1b%/1\x80\x82a\x02x"), "\u{fffd}x");
+    }
+
+    #[test]
+    fn decode_malformed() {
+        let inputs: &[&[u8]] = &[
+            b"\x1b",
+            b"\x1b$",
+            b"\x1b$)",
+            b"\x1b%",
+            b"\x1b%G",
+            b"\x1b%Gabc",
+            b"\x1b%Gab\x1b%",
+            b"\x1b%/",
+            b"\x1b%/1",
+            b"\x1b%/1\x80",
+            b"\x1b%/1\x80\xff",
+            b"\x1b%/1\xff\xff",
+            b"\x1b$)A\xb0",
+            b"\x1b$(Ba",
+            b"\x9b",
+            b"\x9b1",
+            b"\xff\x80\x00\x1b\x1b",
+        ];
+        for input in inputs {
+            decode_compound_text(input);
+        }
+        assert_eq!(decode_compound_text(b"\x1b%Gabc"), "abc");
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(split_strings(b"a\0b\0"), vec![&b"a"[..], b"b"]);
+        assert_eq!(split_strings(b"a\0b"), vec![&b"a"[..], b"b"]);
+        assert_eq!(split_strings(b"a\0\0"), vec![&b"a"[..], b""]);
+        assert!(split_strings(b"").is_empty());
+        assert_eq!(join_strings(&["a", "b"]), b"a\0b\0");
+
+        let atoms = atoms();
+        for (list, encoding) in [
+            (&["a", "é"][..], XCB_ATOM_STRING),
+            (&["ж", "b"][..], atoms.COMPOUND_TEXT),
+        ] {
+            let prop = XcbTextProperty::encode_list(&atoms, list, XcbTextEncoding::CompoundText);
+            assert_eq!(prop.encoding, encoding);
+            assert_eq!(prop.decode_list(&atoms).unwrap(), list);
+        }
+        let prop = XcbTextProperty::encode(&atoms, "日本", XcbTextEncoding::Utf8String);
+        assert_eq!(prop.encoding, atoms.UTF8_STRING);
+        assert_eq!(prop.decode(&atoms).unwrap(), "日本");
+    }
+}